@@ -0,0 +1,127 @@
+use crate::{crypt::ALPHABET, error::{KryptonError, Result}};
+
+/// An ordered set of symbols for ciphers, tableaux and statistics to work over. Index
+/// arithmetic is modulo `len`, so a 25 or 36 symbol alphabet works like A to Z.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet {
+    symbols: Vec<char>,
+    /// Symbols outside the alphabet read as one inside it, like J as I.
+    merges: Vec<(char, char)>,
+}
+
+/// Alphabets offered by front ends, in the order `Alphabet::choice` takes them.
+pub const ALPHABETS: &[&str] = &["A-Z", "A-Z (I/J Merged)", "A-Z 0-9", "Custom"];
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::standard()
+    }
+}
+
+impl Alphabet {
+    pub fn standard() -> Alphabet {
+        Alphabet { symbols: ALPHABET.chars().collect(), merges: Vec::new() }
+    }
+
+    /// A to Z without J, which reads as I.
+    pub fn merged_ij() -> Alphabet {
+        Alphabet { symbols: ALPHABET.chars().filter(|&c| c != 'J').collect(), merges: vec![('J', 'I')] }
+    }
+
+    pub fn alphanumeric() -> Alphabet {
+        Alphabet { symbols: ALPHABET.chars().chain('0'..='9').collect(), merges: Vec::new() }
+    }
+
+    /// Any symbols in the given order, such as `KRYPTOSABCDEFGHIJLMNQUVWXZ`. Whitespace is
+    /// ignored; a repeated symbol or fewer than two symbols is an error.
+    pub fn custom(symbols: &str) -> Result<Alphabet> {
+        let mut alphabet = Alphabet { symbols: Vec::new(), merges: Vec::new() };
+        for c in symbols.chars().filter(|c| !c.is_whitespace()) {
+            if alphabet.symbols.contains(&c) {
+                return Err(KryptonError::Invalid { input: "Alphabet", reason: format!("'{}' appears more than once", c) });
+            }
+            alphabet.symbols.push(c);
+        }
+        if alphabet.symbols.len() < 2 {
+            return Err(KryptonError::TooShort { input: "Alphabet", needed: 2, found: alphabet.symbols.len() });
+        }
+        Ok(alphabet)
+    }
+
+    /// The alphabet named by `ALPHABETS[choice]`, with `custom` giving the symbols of the last.
+    pub fn choice(choice: usize, custom: &str) -> Result<Alphabet> {
+        match choice {
+            0 => Ok(Alphabet::standard()),
+            1 => Ok(Alphabet::merged_ij()),
+            2 => Ok(Alphabet::alphanumeric()),
+            _ => Alphabet::custom(custom),
+        }
+    }
+
+    /// Keyword symbols (first occurrence only) followed by the rest of the alphabet in order.
+    /// Keyword characters outside the alphabet are skipped.
+    pub fn keyed(&self, keyword: &str) -> Alphabet {
+        let mut symbols: Vec<char> = Vec::with_capacity(self.len());
+        for c in keyword.chars().filter_map(|c| self.index(c)).map(|i| self.symbols[i]).chain(self.symbols.iter().copied()) {
+            if !symbols.contains(&c) {
+                symbols.push(c);
+            }
+        }
+        Alphabet { symbols, merges: self.merges.clone() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    /// Position of `c`, trying it as given, then in upper and lower case, then through the merges.
+    pub fn index(&self, c: char) -> Option<usize> {
+        let upper = c.to_ascii_uppercase();
+        let merged = self.merges.iter().find(|&&(from, _)| from == upper).map(|&(_, into)| into);
+        [Some(c), Some(upper), Some(c.to_ascii_lowercase()), merged]
+            .into_iter()
+            .flatten()
+            .find_map(|c| self.symbols.iter().position(|&s| s == c))
+    }
+
+    /// `text` with merged symbols replaced and everything outside the alphabet dropped.
+    pub fn normalize(&self, text: &str) -> String {
+        self.text(&self.indices(text))
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.index(c).is_some()
+    }
+
+    /// The symbol at `index`, wrapping around the end.
+    pub fn symbol(&self, index: usize) -> char {
+        self.symbols[index % self.len()]
+    }
+
+    /// Indices of the characters of `text` that are in the alphabet, dropping the rest.
+    pub fn indices(&self, text: &str) -> Vec<usize> {
+        text.chars().filter_map(|c| self.index(c)).collect()
+    }
+
+    pub fn text(&self, indices: &[usize]) -> String {
+        indices.iter().map(|&i| self.symbol(i)).collect()
+    }
+
+    /// A Vigenere tableau: row 0 is the alphabet and each key symbol adds the alphabet
+    /// started from that symbol. Key characters outside the alphabet are skipped.
+    pub fn tableau(&self, key: &str) -> Vec<Vec<char>> {
+        let mut table = vec![self.symbols.clone()];
+        for k in self.indices(key) {
+            table.push((0..self.len()).map(|j| self.symbol(k + j)).collect());
+        }
+        table
+    }
+}
@@ -1,38 +1,141 @@
 use std::{collections::{HashMap, HashSet}, iter};
 
 use itertools::Itertools;
-
-use crate::{keyless, vig2table, ALPHABET};
-
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alphabet::Alphabet,
+    classify::{aca_stats, classify_stats, AcaStats, CipherGuess},
+    crib::implied_key,
+    error::{check_range, require_text, KryptonError, Result},
+    jobs::{Monitor, Unmonitored},
+    ngram::english_fitness,
+    significance::{significance_monitored, Significance, SignificanceConfig},
+    toolkit::Family,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AnalysisResult {
     pub chi_score: f64,
     pub match_score: f64,
-    pub kasiski: Vec<usize>,
+    pub kasiski: KasiskiReport,
     pub friedman: (usize,f64),
     pub key_elim: (usize, f64, String),
-    pub IOC: f64, 
+    pub ioc: f64,
     pub phi: (usize, f64),
+    pub ioc_curve: Vec<PeriodIoc>,
     pub aster: f64,
     pub substitution_match: f64,
+    pub fitness: f64,
+    pub aca: AcaStats,
+    pub cipher_types: Vec<CipherGuess>,
+    pub significance: Vec<Significance>,
+}
+
+impl AnalysisResult {
+    /// Names of the values of `statistics()`, as in the ANALYSIS panel.
+    pub const STATISTICS: [&'static str; 19] = [
+        "CHI SCORE",
+        "MATCH SCORE",
+        "KASISKI TOP WEIGHT",
+        "FRIEDMAN TEST",
+        "KEY ELIMINATION",
+        "INCIDENCE OF COINCIDENCE",
+        "AVG INDEX OF COINCIDENCE",
+        "ASTER SCORE",
+        "SUBSTITUTION SCORE",
+        "QUADGRAM FITNESS",
+        "ACA IC",
+        "ACA MIC",
+        "ACA MKA",
+        "ACA DIC",
+        "ACA EDI",
+        "ACA LR",
+        "ACA ROD",
+        "ACA LDI",
+        "ACA SDD",
+    ];
+
+    /// Every single-number statistic, named as in the ANALYSIS panel.
+    pub fn statistics(&self) -> Vec<(&'static str, f64)> {
+        let values = [
+            self.chi_score,
+            self.match_score,
+            self.kasiski.factors.first().map_or(0.0, |&(_, weight)| weight),
+            self.friedman.1,
+            self.key_elim.1,
+            self.ioc,
+            self.phi.1,
+            self.aster,
+            self.substitution_match,
+            self.fitness,
+            self.aca.ic,
+            self.aca.mic,
+            self.aca.mka,
+            self.aca.dic,
+            self.aca.edi,
+            self.aca.lr,
+            self.aca.rod,
+            self.aca.ldi,
+            self.aca.sdd,
+        ];
+        Self::STATISTICS.into_iter().zip(values).collect()
+    }
 }
 
-pub fn analyze(                                                                                                                      
+pub fn analyze(
     encrypted_text: &str,
     plaintext: &str,
     max_key_length: usize,
-) -> AnalysisResult {    
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+    config: &SignificanceConfig,
+) -> AnalysisResult {
+    analyze_monitored(encrypted_text, plaintext, max_key_length, excluded_factors, alphabet, config, &Unmonitored)
+}
+
+/// `analyze` reporting the progress of its significance trials, which stop early when
+/// cancelled.
+pub fn analyze_monitored(
+    encrypted_text: &str,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+    config: &SignificanceConfig,
+    monitor: &dyn Monitor,
+) -> AnalysisResult {
+    // Spacing, punctuation and `?` would shift every positional statistic.
+    let encrypted_text = &alphabet.normalize(encrypted_text);
+
+    let mut result = measure(encrypted_text, plaintext, max_key_length, excluded_factors, alphabet);
+    result.significance = significance_monitored(&result, encrypted_text, plaintext, max_key_length, excluded_factors, alphabet, config, monitor);
+    result
+}
 
+/// Everything `analyze` reports except the significance tests. Kasiski, Friedman and the
+/// coincidence indices count symbols of `alphabet`; the chi score, fitness and ACA
+/// statistics are measured against English A to Z.
+fn measure(
+    encrypted_text: &str,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+) -> AnalysisResult {
     let chi_score = chi_squared_score(encrypted_text);
     let match_score = match_percentage(plaintext, encrypted_text);
-    let kasiski = kasiski_examination(encrypted_text, &[1, 2, 4]);
-    let friedman = friedman_key_length(encrypted_text, max_key_length);
+    let kasiski = kasiski_examination(encrypted_text, alphabet, excluded_factors);
+    let friedman = friedman_key_length(encrypted_text, alphabet, max_key_length);
     let key_elim = key_elimation(max_key_length, encrypted_text, plaintext);
-    let IOC = index_of_coincidence(encrypted_text);
-    let phi = best_phi(encrypted_text, max_key_length);
+    let ioc = index_of_coincidence(&alphabet.indices(encrypted_text), alphabet);
+    let phi = best_phi(encrypted_text, alphabet, max_key_length);
+    let ioc_curve = periodic_ioc(encrypted_text, alphabet, max_key_length);
     let aster = aster_score(encrypted_text, plaintext);
     let substitution_match = substitution_cipher_score(encrypted_text, plaintext).unwrap_or(0.0);
-
-    println!("{:?}", transpose_string(encrypted_text, max_key_length));
+    let fitness = english_fitness(encrypted_text);
+    let aca = aca_stats(encrypted_text);
+    let cipher_types = classify_stats(&aca);
 
     AnalysisResult {
         chi_score,
@@ -40,26 +143,68 @@ pub fn analyze(
         kasiski,
         friedman,
         key_elim,
-        IOC, 
+        ioc,
         phi,
+        ioc_curve,
         aster,
         substitution_match,
+        fitness,
+        aca,
+        cipher_types,
+        significance: Vec::new(),
     }
-}           
+}
+
+/// The values of `AnalysisResult::statistics` for a text, without the reports that only
+/// `measure` needs, such as the IoC curve and the cipher type guesses. The significance
+/// trials run this many times.
+pub(crate) fn statistic_values(
+    encrypted_text: &str,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+) -> [f64; AnalysisResult::STATISTICS.len()] {
+    let aca = aca_stats(encrypted_text);
+    [
+        chi_squared_score(encrypted_text),
+        match_percentage(plaintext, encrypted_text),
+        kasiski_examination(encrypted_text, alphabet, excluded_factors).factors.first().map_or(0.0, |&(_, weight)| weight),
+        friedman_key_length(encrypted_text, alphabet, max_key_length).1,
+        key_elimation(max_key_length, encrypted_text, plaintext).1,
+        index_of_coincidence(&alphabet.indices(encrypted_text), alphabet),
+        best_phi(encrypted_text, alphabet, max_key_length).1,
+        aster_score(encrypted_text, plaintext),
+        substitution_cipher_score(encrypted_text, plaintext).unwrap_or(0.0),
+        english_fitness(encrypted_text),
+        aca.ic,
+        aca.mic,
+        aca.mka,
+        aca.dic,
+        aca.edi,
+        aca.lr,
+        aca.rod,
+        aca.ldi,
+        aca.sdd,
+    ]
+}
+
+/// English letter frequencies, A to Z.
+pub const ENGLISH_FREQUENCIES: [f64; 26] = [
+    0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015,
+    0.06094, 0.06966, 0.00153, 0.00772, 0.04025, 0.02406, 0.06749,
+    0.07507, 0.01929, 0.00095, 0.05987, 0.06327, 0.09056, 0.02758,
+    0.00978, 0.02360, 0.00150, 0.01974, 0.00074,
+];
 
 pub fn chi_squared_score(encrypted_text: &str) -> f64 {
-    let frequencies = [
-        0.08167, 0.01492, 0.02782, 0.04253, 0.12702, 0.02228, 0.02015,
-        0.06094, 0.06966, 0.00153, 0.00772, 0.04025, 0.02406, 0.06749,
-        0.07507, 0.01929, 0.00095, 0.05987, 0.06327, 0.09056, 0.02758,
-        0.00978, 0.02360, 0.00150, 0.01974, 0.00074,
-    ];
+    let frequencies = ENGLISH_FREQUENCIES;
 
     let mut counts = [0.0; 26];
     let total_count = encrypted_text
         .as_bytes()
         .iter()
-        .filter(|&b| matches!(b, b'A'..=b'Z' | b'a'..=b'z'))
+        .filter(|b| b.is_ascii_alphabetic())
         .fold(0.0, |acc, &b| {
             counts[(b.to_ascii_uppercase() - b'A') as usize] += 1.0;
             acc + 1.0
@@ -99,78 +244,98 @@ pub fn match_percentage(str1: &str, str2: &str) -> f64 {
     (matches as f64 / max_length as f64) * 100.0
 }
 
-pub fn kasiski_examination(ciphertext: &str, excluded_factors: &[usize]) -> Vec<usize> {
-    let mut substring_positions: HashMap<&str, Vec<usize>> = HashMap::new();
-    let mut distances: Vec<f64> = Vec::new();
+/// One repeated sequence from a Kasiski examination. Positions are letter offsets in the
+/// text with non-letters removed; distances are between pairs of positions that are not
+/// already part of a longer repeat.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KasiskiRepeat {
+    pub sequence: String,
+    pub positions: Vec<usize>,
+    pub distances: Vec<usize>,
+}
 
-    // Set for fast exclusion checks
-    let excluded_factors_set: HashSet<usize> = excluded_factors.iter().cloned().collect();
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KasiskiReport {
+    pub repeats: Vec<KasiskiRepeat>,
+    /// Factors of the distances, each weighted by the length of the repeated sequence,
+    /// heaviest first.
+    pub factors: Vec<(usize, f64)>,
+}
 
-    // Find repeating substrings and their positions
-    for i in 0..ciphertext.len() - 2 {
-        for j in 3..=10 {
-            if i + j <= ciphertext.len() {
-                let substring = &ciphertext[i..i + j];
-                substring_positions.entry(substring)
-                    .or_insert_with(Vec::new)
-                    .push(i);
-            }
-        }
+impl KasiskiReport {
+    /// The `n` heaviest factors.
+    pub fn top(&self, n: usize) -> Vec<usize> {
+        self.factors.iter().take(n).map(|&(factor, _)| factor).collect()
     }
+}
 
-    // Calculate distances between repeating substrings and normalize them
-    let ciphertext_length = ciphertext.len() as f64;
-    for positions in substring_positions.values() {
-        for (i, j) in positions.iter().tuple_combinations() {
-            let distance = (j - i) as f64 / ciphertext_length;
-            distances.push(distance);
+/// Shortest and longest sequences looked for by `kasiski_examination`.
+const KASISKI_LENGTHS: (usize, usize) = (3, 10);
+
+/// Finds every repeated sequence of 3 to 10 letters, longest first, with the distances
+/// between repeats and a histogram of their factors. A shorter repeat lying inside a longer
+/// one at the same distance is not counted again. `excluded_factors` are left out of the
+/// histogram, and 1 never appears in it. Only symbols of `alphabet` are counted.
+pub fn kasiski_examination(ciphertext: &str, alphabet: &Alphabet, excluded_factors: &[usize]) -> KasiskiReport {
+    let letters: Vec<char> = alphabet.text(&alphabet.indices(ciphertext)).chars().collect();
+    let (shortest, longest) = KASISKI_LENGTHS;
+    let mut covered: HashSet<(usize, usize)> = HashSet::new();
+    let mut repeats = Vec::new();
+    let mut factor_weights: HashMap<usize, f64> = HashMap::new();
+
+    for length in (shortest..=longest.min(letters.len())).rev() {
+        let mut positions: HashMap<&[char], Vec<usize>> = HashMap::new();
+        for (i, window) in letters.windows(length).enumerate() {
+            positions.entry(window).or_default().push(i);
         }
-    }
-
-    // Find the most common factors among the normalized distances, excluding the specified factors
-    let mut factor_counts: HashMap<usize, f64> = HashMap::new();
-    for &distance in &distances {
-        for factor in (2..=(distance * ciphertext_length) as usize).filter(|&i| (distance * ciphertext_length) as usize % i == 0) {
-            if !excluded_factors_set.contains(&factor) {
-                *factor_counts.entry(factor).or_insert(0.0) += 1.0;
+        let mut found: Vec<(&[char], Vec<usize>)> = positions.into_iter().filter(|(_, p)| p.len() > 1).collect();
+        found.sort_by_key(|(_, p)| p[0]);
+
+        for (sequence, positions) in found {
+            let distances: Vec<usize> = positions
+                .iter()
+                .tuple_combinations()
+                .filter(|&(&a, &b)| !covered.contains(&(a, b)))
+                .map(|(&a, &b)| b - a)
+                .collect();
+            for (&a, &b) in positions.iter().tuple_combinations() {
+                for offset in 0..=length - shortest {
+                    covered.insert((a + offset, b + offset));
+                }
+            }
+            if distances.is_empty() {
+                continue;
+            }
+            for &distance in &distances {
+                for factor in (2..=distance).filter(|f| distance % f == 0 && !excluded_factors.contains(f)) {
+                    *factor_weights.entry(factor).or_insert(0.0) += length as f64;
+                }
             }
+            repeats.push(KasiskiRepeat { sequence: sequence.iter().collect(), positions, distances });
         }
     }
 
-    // Normalize the factor counts
-    for count in factor_counts.values_mut() {
-        *count /= distances.len() as f64;
-    }
-
-    // Sort the factors by their normalized frequencies in descending order
-    let mut sorted_factors: Vec<(usize, f64)> = factor_counts.into_iter().collect();
-    sorted_factors.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-
-    // Return the top 4 factors
-    sorted_factors.into_iter().take(4).map(|(factor, _)| factor).collect()
+    let mut factors: Vec<(usize, f64)> = factor_weights.into_iter().collect();
+    factors.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    KasiskiReport { repeats, factors }
 }
 
-fn friedman_test(text: &str) -> f64 {
-    let text = text.to_uppercase();
+fn friedman_test(text: &[usize], alphabet: &Alphabet) -> f64 {
     let len = text.len() as f64;
-    let mut freq = [0; 26];
+    let mut freq = vec![0; alphabet.len()];
 
     // Count frequency of each letter
-    for c in text.chars() {
-        if c.is_ascii_alphabetic() {
-            freq[(c as u8 - b'A') as usize] += 1;
-        }
+    for &c in text {
+        freq[c] += 1;
     }
 
     // Calculate the sum for the index of coincidence
     let sum: f64 = freq.iter().map(|&count| (count as f64) * (count as f64 - 1.0)).sum();
-    let index_of_coincidence = sum / (len * (len - 1.0));
-
-    index_of_coincidence
+    sum / (len * (len - 1.0))
 }
 
-pub fn friedman_key_length(text: &str, max_key_length: usize) -> (usize, f64) {
-    let text = text.to_uppercase();
+pub fn friedman_key_length(text: &str, alphabet: &Alphabet, max_key_length: usize) -> (usize, f64) {
+    let text = alphabet.indices(text);
     let mut avg_ics = Vec::with_capacity(max_key_length);
 
     // Calculate average IC for each key length
@@ -179,8 +344,8 @@ pub fn friedman_key_length(text: &str, max_key_length: usize) -> (usize, f64) {
 
         // Calculate IC for each column in the key length
         for i in 0..key_length {
-            let column: String = text.chars().skip(i).step_by(key_length).collect();
-            sum_ic += friedman_test(&column);
+            let column: Vec<usize> = text.iter().skip(i).step_by(key_length).copied().collect();
+            sum_ic += friedman_test(&column, alphabet);
         }
 
         let avg_ic = sum_ic / key_length as f64;
@@ -209,35 +374,27 @@ pub fn friedman_key_length(text: &str, max_key_length: usize) -> (usize, f64) {
 }
 
 fn shift_and_subtract(s: &str, n: usize) -> String {
-    let shifted = s.chars().skip(n).chain(iter::repeat('_').take(n)).collect::<String>();
+    let shifted = s.chars().skip(n).chain(iter::repeat_n('_', n)).collect::<String>();
     let mut result = String::with_capacity(s.len());
     for (c1, c2) in s.chars().zip(shifted.chars()) {
         if c2 == '_' {
             result.push(c1);
         } else {
-            let diff = ((c1 as u8 - b'A') - (c2 as u8 - b'A') + 26) % 26;
-            result.push((diff + b'A') as char);
+            result.push(letter_difference(c1, c2));
         }
     }
 
-    result.truncate(s.len() - n);
+    result.truncate(s.len().saturating_sub(n));
     if !result.is_empty() {
         result.remove(0);
     }
     result
 }
 
-fn subtract_strings(s1: &str, s2: &str) -> String {
-    let len = s1.len().max(s2.len());
-    s1.chars()
-        .chain(iter::repeat('A'))
-        .zip(s2.chars().chain(iter::repeat('A')))
-        .take(len)
-        .map(|(c1, c2)| {
-            let diff = ((c1 as u8 - b'A') - (c2 as u8 - b'A') + 26) % 26;
-            (diff + b'A') as char
-        })
-        .collect()
+/// `c1 - c2` as a letter, A being 0. Worked in signed arithmetic so that non-letters such
+/// as `_` or `?` give a wrong letter rather than an overflow.
+fn letter_difference(c1: char, c2: char) -> char {
+    (b'A' + (c1 as i32 - c2 as i32).rem_euclid(26) as u8) as char
 }
 
 pub fn key_elimation(max_key_length: usize, encrypted_text: &str, plaintext: &str) -> (usize, f64, String) {
@@ -256,7 +413,7 @@ pub fn key_elimation(max_key_length: usize, encrypted_text: &str, plaintext: &st
 
         if normalized_score > best_score {
             best_score = normalized_score;
-            best_sequence = subtract_strings(encrypted_text, plaintext).chars().take(i).collect();
+            best_sequence = implied_key(&Alphabet::standard(), Family::Vigenere, encrypted_text, plaintext).chars().take(i).collect();
             best_key_length = i;
         }
     }
@@ -264,35 +421,10 @@ pub fn key_elimation(max_key_length: usize, encrypted_text: &str, plaintext: &st
     (best_key_length, best_score, best_sequence)
 }
 
-fn decrypt_vigenere(encrypted_text: &str, key: &str) -> String {
-    let key_len = key.len();
-    let mut decrypted = String::with_capacity(encrypted_text.len());
-
-    for (i, ch) in encrypted_text.chars().enumerate() {
-        if ch.is_ascii_alphabetic() {
-            let shift = key.chars().nth(i % key_len).unwrap() as u8 - b'A';
-            let decrypted_char = decrypt_char(ch, shift);
-            decrypted.push(decrypted_char);
-        } else {
-            decrypted.push(ch);
-        }
-    }
-
-    decrypted
-}
-
-fn decrypt_char(ch: char, shift: u8) -> char {
-    if ch.is_ascii_lowercase() {
-        ((ch as u8 - b'a' - shift + 26) % 26 + b'a') as char
-    } else {
-        ((ch as u8 - b'A' - shift + 26) % 26 + b'A') as char
-    }
-}
-
 pub fn percentage_blocks(value: f64, min: f64, max: f64) -> String {
-    let bounded_value = value.clamp(min, max);
+    let bounded_value = value.max(min).min(max);
     let percentage = (bounded_value - min) / (max - min);
-    let filled_blocks = (percentage * 10.0).round() as usize;
+    let filled_blocks = ((percentage * 10.0).round() as usize).min(10);
     let empty_blocks = 10 - filled_blocks;
 
     let filled_str = "■".repeat(filled_blocks);
@@ -301,16 +433,13 @@ pub fn percentage_blocks(value: f64, min: f64, max: f64) -> String {
     format!("[{}{}]", filled_str, empty_str)
 }
 
-fn index_of_coincidence(text: &str) -> f64 {
-    let mut freq = [0; 26];
-    let mut total = 0;
+/// IoC scaled by the alphabet size and divided by English's 1.73.
+fn index_of_coincidence(text: &[usize], alphabet: &Alphabet) -> f64 {
+    let mut freq = vec![0; alphabet.len()];
+    let total = text.len();
 
-    for c in text.chars() {
-        if c.is_ascii_alphabetic() {
-            let idx = (c.to_ascii_lowercase() as u8 - b'a') as usize;
-            freq[idx] += 1;
-            total += 1;
-        }
+    for &c in text {
+        freq[c] += 1;
     }
     let mut sum = 0.0;
     for &f in &freq {
@@ -319,19 +448,16 @@ fn index_of_coincidence(text: &str) -> f64 {
 
     let ic = sum / (total as f64 * (total as f64 - 1.0));
 
-    (ic * 26.0) / 1.73
+    (ic * alphabet.len() as f64) / 1.73
 }
 
-fn phi_test(text: &str, period: usize) -> f64 {
-    let text = text.to_ascii_uppercase();
-    let text: String = text.chars().filter(|c| c.is_ascii_alphabetic()).collect();
-
-    let ic_total = index_of_coincidence(&text);
+fn phi_test(text: &[usize], alphabet: &Alphabet, period: usize) -> f64 {
+    let ic_total = index_of_coincidence(text, alphabet);
     let mut ic_sum = 0.0;
 
     for i in 0..period {
-        let column: String = text.chars().skip(i).step_by(period).collect();
-        let ic_column = index_of_coincidence(&column);
+        let column: Vec<usize> = text.iter().skip(i).step_by(period).copied().collect();
+        let ic_column = index_of_coincidence(&column, alphabet);
         ic_sum += ic_column;
     }
 
@@ -339,12 +465,50 @@ fn phi_test(text: &str, period: usize) -> f64 {
     ic_avg / ic_total
 }
 
-pub fn best_phi(text: &str, max_key_length: usize) -> (usize, f64) {
+/// Index of coincidence of every column at one period.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PeriodIoc {
+    pub period: usize,
+    pub average: f64,
+    pub columns: Vec<f64>,
+}
+
+/// Column IoC scaled by the alphabet size, so random text sits near 1.0 and English near
+/// 1.73. Columns with fewer than two letters have no IoC and score 0.
+pub(crate) fn column_ioc(column: &[usize], size: usize) -> f64 {
+    if column.len() < 2 {
+        return 0.0;
+    }
+    let mut freq = vec![0usize; size];
+    for &c in column {
+        freq[c] += 1;
+    }
+    let sum: usize = freq.iter().map(|&f| f * f.saturating_sub(1)).sum();
+    size as f64 * sum as f64 / (column.len() * (column.len() - 1)) as f64
+}
+
+/// Average and per-column IoC for every period from 1 to `max_period`. Peaks at the key
+/// length and its multiples stand out against the 1.0 of random columns.
+pub fn periodic_ioc(text: &str, alphabet: &Alphabet, max_period: usize) -> Vec<PeriodIoc> {
+    let letters = alphabet.indices(text);
+    (1..=max_period.min(letters.len()))
+        .map(|period| {
+            let columns: Vec<f64> = (0..period)
+                .map(|i| column_ioc(&letters.iter().skip(i).step_by(period).copied().collect::<Vec<usize>>(), alphabet.len()))
+                .collect();
+            let average = columns.iter().sum::<f64>() / period as f64;
+            PeriodIoc { period, average, columns }
+        })
+        .collect()
+}
+
+pub fn best_phi(text: &str, alphabet: &Alphabet, max_key_length: usize) -> (usize, f64) {
+    let text = alphabet.indices(text);
     let mut best_period = 0;
     let mut best_score = 0.0;
 
     for period in 1..=max_key_length {
-        let score = phi_test(text, period);
+        let score = phi_test(&text, alphabet, period);
         if score > best_score {
             best_score = score;
             best_period = period;
@@ -369,7 +533,8 @@ pub fn aster_score(encrypted_text: &str, plaintext: &str) -> f64 {
         if c1 == c2 {
             score += 1.0;
         } else {
-            let dist = ((c1 as u8 - c2 as u8 + 26) % 26).min((c2 as u8 - c1 as u8 + 26) % 26);
+            let diff = (c1 as i32 - c2 as i32).rem_euclid(26);
+            let dist = diff.min(26 - diff);
             score += 1.0 / (dist as f64 + 1.0);
         }
     }
@@ -423,124 +588,146 @@ pub fn substitution_cipher_score(str1: &str, str2: &str) -> Option<f64> {
     Some(score)
 }
 
-fn find_substitution_key(plaintext: &str, ciphertext: &str) -> Option<[char; 26]> {
-    if plaintext.len() != ciphertext.len() {
-        return None;
-    }
-
-    let mut key = ['_'; 26];
-    let mut used = [false; 26];
-
-    for (p, c) in plaintext.chars().zip(ciphertext.chars()) {
-        if !p.is_ascii_alphabetic() || !c.is_ascii_alphabetic() {
-            continue;
-        }
+// Checked versions of the public functions above, for front ends that report bad input
+// instead of showing NaN scores or empty reports.
 
-        let p_idx = (p.to_ascii_lowercase() as u8 - b'a') as usize;
-        let c_idx = (c.to_ascii_lowercase() as u8 - b'a') as usize;
-
-        if key[p_idx] == '_' {
-            if used[c_idx] {
-                return None;
-            }
-            key[p_idx] = c.to_ascii_lowercase();
-            used[c_idx] = true;
-        } else if key[p_idx] != c.to_ascii_lowercase() {
-            return None;
-        }
-    }
+/// Texts for a statistic measured over A to Z must have at least one letter.
+fn require_letters(input: &'static str, text: &str) -> Result<()> {
+    require_text(input, text, &Alphabet::standard())
+}
 
-    if key.contains(&'_') {
-        return None;
+/// Splitting the text into `max_key_length` columns must leave two letters in each.
+fn check_columns(text: &str, alphabet: &Alphabet, max_key_length: usize) -> Result<()> {
+    require_text("Encrypted", text, alphabet)?;
+    let found = alphabet.indices(text).len();
+    if found < 2 {
+        return Err(KryptonError::TooShort { input: "Encrypted", needed: 2, found });
     }
+    check_range("Max Key Length", max_key_length, 1, found / 2)
+}
 
-    Some(key)
+pub fn try_analyze(
+    encrypted_text: &str,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+    config: &SignificanceConfig,
+) -> Result<AnalysisResult> {
+    try_analyze_monitored(encrypted_text, plaintext, max_key_length, excluded_factors, alphabet, config, &Unmonitored)
 }
 
-fn odd_indexed_string(s: &str) -> String {
-    s.char_indices()
-        .filter(|(i, _)| i % 2 == 1)
-        .map(|(_, c)| c)
-        .collect()
+pub fn try_analyze_monitored(
+    encrypted_text: &str,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+    config: &SignificanceConfig,
+    monitor: &dyn Monitor,
+) -> Result<AnalysisResult> {
+    check_columns(encrypted_text, alphabet, max_key_length)?;
+    Ok(analyze_monitored(encrypted_text, plaintext, max_key_length, excluded_factors, alphabet, config, monitor))
 }
 
-fn even_indexed_string(s: &str) -> String {
-    s.char_indices()
-        .filter(|(i, _)| i % 2 == 0)
-        .map(|(_, c)| c)
-        .collect()
+pub fn try_chi_squared_score(encrypted_text: &str) -> Result<f64> {
+    require_letters("Encrypted", encrypted_text)?;
+    Ok(chi_squared_score(encrypted_text))
 }
 
-fn caesar_shift(text: &str, shift: i32) -> String {
-    let alphabet_upper = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+pub fn try_match_percentage(str1: &str, str2: &str) -> Result<f64> {
+    if str1.chars().chain(str2.chars()).all(|c| c == ' ') {
+        return Err(KryptonError::EmptyText("Text"));
+    }
+    Ok(match_percentage(str1, str2))
+}
 
-    text.chars()
-        .map(|c| {
-            if c.is_ascii_uppercase() {
-                let index = alphabet_upper.find(c).unwrap();
-                let shifted_index = (index as i32 + shift) % 26;
-                alphabet_upper.chars().nth(shifted_index as usize).unwrap()
-            } else {
-                c
-            }
-        })
-        .collect()
+pub fn try_kasiski_examination(ciphertext: &str, alphabet: &Alphabet, excluded_factors: &[usize]) -> Result<KasiskiReport> {
+    require_text("Encrypted", ciphertext, alphabet)?;
+    Ok(kasiski_examination(ciphertext, alphabet, excluded_factors))
 }
 
-fn transpose_string(input: &str, n: usize) -> Vec<Vec<char>>{
-    let inputvec = input.chars().collect::<Vec<char>>();
-    let length = inputvec.len();
+pub fn try_friedman_key_length(text: &str, alphabet: &Alphabet, max_key_length: usize) -> Result<(usize, f64)> {
+    check_columns(text, alphabet, max_key_length)?;
+    Ok(friedman_key_length(text, alphabet, max_key_length))
+}
 
-    let rows = (length as f64 / n as f64).floor() as usize;
+/// Both texts must be longer than `max_key_length`, or the shifted differences are empty.
+pub fn try_key_elimation(max_key_length: usize, encrypted_text: &str, plaintext: &str) -> Result<(usize, f64, String)> {
+    let found = encrypted_text.chars().count().min(plaintext.chars().count());
+    if found < 2 {
+        return Err(KryptonError::TooShort { input: "Text", needed: 2, found });
+    }
+    check_range("Max Key Length", max_key_length, 1, found - 1)?;
+    Ok(key_elimation(max_key_length, encrypted_text, plaintext))
+}
 
-    let mut intermediate = vec![vec![' '; n]; rows];
-    let mut index = 0; 
-    for i in 0..rows {
-        for j in 0..n {
-            if index < length {
-                intermediate[i][j] = inputvec[index];
-                index += 1;
-            }
-        }
+pub fn try_percentage_blocks(value: f64, min: f64, max: f64) -> Result<String> {
+    if min.is_nan() || max.is_nan() || min >= max {
+        return Err(KryptonError::Invalid { input: "Range", reason: format!("{} is not below {}", min, max) });
     }
-    let resultSize = intermediate[0].len();
-    let resultInner = intermediate.len();
+    Ok(percentage_blocks(value, min, max))
+}
 
-    let mut result: Vec<Vec<char>> = vec![vec![' '; resultInner]; resultSize];
+pub fn try_periodic_ioc(text: &str, alphabet: &Alphabet, max_period: usize) -> Result<Vec<PeriodIoc>> {
+    require_text("Encrypted", text, alphabet)?;
+    check_range("Max Key Length", max_period, 1, alphabet.indices(text).len())?;
+    Ok(periodic_ioc(text, alphabet, max_period))
+}
 
-    for i in 0..resultInner {
-        for j in 0..resultSize {
-            result[j][i] = intermediate[i][j];
-        }
-    }
-    result
+pub fn try_best_phi(text: &str, alphabet: &Alphabet, max_key_length: usize) -> Result<(usize, f64)> {
+    check_columns(text, alphabet, max_key_length)?;
+    Ok(best_phi(text, alphabet, max_key_length))
 }
 
-fn ioc(text: &str) -> f64 {
-    // Filter the text to include only alphabetic characters and convert to lowercase
-    let filtered_text: Vec<char> = text
-        .chars()
-        .filter(|c| c.is_alphabetic())
-        .map(|c| c.to_ascii_lowercase())
-        .collect();
+pub fn try_aster_score(encrypted_text: &str, plaintext: &str) -> Result<f64> {
+    require_letters("Encrypted", encrypted_text)?;
+    require_letters("Plaintext", plaintext)?;
+    Ok(aster_score(encrypted_text, plaintext))
+}
 
-    let length = filtered_text.len();
-    if length <= 1 {
-        return 0.0;
+/// Says why the texts cannot be compared where `substitution_cipher_score` gives `None`.
+pub fn try_substitution_cipher_score(str1: &str, str2: &str) -> Result<f64> {
+    if str1.len() != str2.len() {
+        return Err(KryptonError::LengthMismatch { left: str1.len(), right: str2.len() });
     }
-
-    // Calculate frequency of each character
-    let mut frequencies = [0; 26];
-    for &c in &filtered_text {
-        frequencies[(c as usize) - ('a' as usize)] += 1;
+    for (input, text) in [("Encrypted", str1), ("Plaintext", str2)] {
+        if let Some(symbol) = text.chars().find(|&c| c != '_' && !c.is_ascii_uppercase()) {
+            return Err(KryptonError::NotInAlphabet { input, symbol });
+        }
     }
+    substitution_cipher_score(str1, str2).ok_or(KryptonError::Invalid { input: "Text", reason: "cannot be compared".to_string() })
+}
 
-    // Calculate the Index of Coincidence
-    let mut ic = 0.0;
-    for &freq in &frequencies {
-        ic += (freq * (freq - 1)) as f64;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypt::{K2, K2p},
+        vigenere::vigenere,
+    };
+
+    #[test]
+    fn statistic_values_match_the_full_analysis() {
+        let result = measure(K2, K2p, 12, &[2], &Alphabet::standard());
+        let values: Vec<f64> = result.statistics().into_iter().map(|(_, value)| value).collect();
+        assert_eq!(values, statistic_values(K2, K2p, 12, &[2], &Alphabet::standard()));
+    }
+
+    #[test]
+    fn kasiski_finds_the_period_of_a_repeated_phrase() {
+        // ATTACKATDAWN starts at 0, 18 and 42, so its encryptions under a key of period
+        // 6 repeat at multiples of 6, as sequences of up to 10 letters.
+        let plaintext = "ATTACKATDAWNXQJZVBATTACKATDAWNMFKPWGYLRHUEATTACKATDAWN";
+        let ciphertext = vigenere(plaintext, "CIPHER");
+        let report = kasiski_examination(&ciphertext, &Alphabet::standard(), &[]);
+        let longest = &report.repeats[0];
+        assert_eq!(longest.sequence, ciphertext[..10]);
+        assert_eq!(longest.positions, [0, 18, 42]);
+        assert_eq!(longest.distances, [18, 42, 24]);
+        assert!(report.top(3).contains(&6), "{:?}", report.factors);
+
+        let report = kasiski_examination(&ciphertext, &Alphabet::standard(), &[2, 3]);
+        assert_eq!(report.top(1), [6]);
     }
-    ic /= (length * (length - 1)) as f64;
-
-    ic
-}
\ No newline at end of file
+}
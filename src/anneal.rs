@@ -0,0 +1,223 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alphabet::Alphabet,
+    jobs::{Monitor, Unmonitored},
+    ngram::NgramModel,
+};
+
+/// Settings for `anneal_keyed_vigenere`. The temperature falls geometrically from
+/// `start_temperature` to `end_temperature` over `steps` steps in every restart.
+/// Temperatures are per 100 letters of ciphertext, so one schedule suits any length.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnealConfig {
+    pub keyword1_length: usize,
+    pub keyword2_length: usize,
+    pub start_temperature: f64,
+    pub end_temperature: f64,
+    pub steps: usize,
+    pub restarts: usize,
+    pub seed: u64,
+    pub keep: usize,
+}
+
+impl Default for AnnealConfig {
+    fn default() -> Self {
+        AnnealConfig {
+            keyword1_length: 7,
+            keyword2_length: 8,
+            start_temperature: 3.0,
+            end_temperature: 0.15,
+            steps: 100000,
+            restarts: 5,
+            seed: 1,
+            keep: 5,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct KeyPair {
+    pub keyword1: String,
+    pub keyword2: String,
+    pub plaintext: String,
+    pub score: f64,
+}
+
+/// The keyed alphabet, as indices into the base alphabet, and the period key as shifts
+/// into it. Keeping shifts rather than key letters means an alphabet swap leaves the other
+/// columns' key letters where they are.
+#[derive(Clone)]
+struct State {
+    alphabet: Vec<usize>,
+    shifts: Vec<usize>,
+}
+
+impl State {
+    fn random(rng: &mut StdRng, base: &Alphabet, config: &AnnealConfig) -> State {
+        let size = base.len();
+        let keyword1: Vec<usize> = (0..config.keyword1_length).map(|_| rng.gen_range(0..size)).collect();
+        State {
+            alphabet: base.indices(&base.keyed(&base.text(&keyword1)).symbols().iter().collect::<String>()),
+            shifts: (0..config.keyword2_length.max(1)).map(|_| rng.gen_range(0..size)).collect(),
+        }
+    }
+
+    /// Decrypts as `vigenere_two_decrypt_in` does, returning plaintext indices into the base alphabet.
+    fn decrypt(&self, cipher: &[usize]) -> Vec<usize> {
+        let size = self.alphabet.len();
+        let mut position = vec![0; size];
+        for (i, &c) in self.alphabet.iter().enumerate() {
+            position[c] = i;
+        }
+        cipher
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| self.alphabet[(position[c] + size - self.shifts[i % self.shifts.len()]) % size])
+            .collect()
+    }
+
+    /// Swaps two symbols of the alphabet or changes one shift of the period key.
+    fn mutate(&mut self, rng: &mut StdRng) {
+        let size = self.alphabet.len();
+        if rng.gen_range(0..4) == 0 {
+            let i = rng.gen_range(0..self.shifts.len());
+            self.shifts[i] = rng.gen_range(0..size);
+        } else {
+            let i = rng.gen_range(0..size);
+            let j = rng.gen_range(0..size);
+            self.alphabet.swap(i, j);
+        }
+    }
+
+    /// Keywords for the current state. Rotating the alphabet together with the key letters
+    /// gives the same decryption, so the rotation with the shortest keyword is reported:
+    /// the shortest prefix after which the remaining letters are in order.
+    fn keywords(&self, base: &Alphabet) -> (String, String) {
+        let size = self.alphabet.len();
+        let (rotation, split) = (0..size)
+            .map(|r| {
+                let mut rotated = self.alphabet.clone();
+                rotated.rotate_left(r);
+                let split = (0..=size).find(|&s| rotated[s..].windows(2).all(|w| w[0] < w[1])).unwrap_or(size);
+                (r, split)
+            })
+            .min_by_key(|&(_, split)| split)
+            .unwrap_or((0, size));
+        let mut rotated = self.alphabet.clone();
+        rotated.rotate_left(rotation);
+        // A shift counts along the rotated alphabet just as along the original, so the key
+        // letters come from the rotation reported.
+        let keyword2 = self.shifts.iter().map(|&s| rotated[s]).collect::<Vec<usize>>();
+        (base.text(&rotated[..split]), base.text(&keyword2))
+    }
+}
+
+/// Quadgram score of plaintext given as base alphabet indices, with `letters` mapping each
+/// index to its English letter. Symbols that are not letters cannot be scored and are skipped.
+fn score(model: &NgramModel, letters: &[Option<usize>], plain: &[usize]) -> f64 {
+    model.score_indices(&plain.iter().filter_map(|&p| letters[p]).collect::<Vec<usize>>())
+}
+
+/// Simulated annealing over both keywords of the K1/K2 style keyed Vigenere
+/// (`vigenere_two_decrypt`), scored by quadgram fitness of the decryption alone.
+/// Returns up to `config.keep` distinct keyword pairs, best first.
+pub fn anneal_keyed_vigenere(ciphertext: &str, config: &AnnealConfig) -> Vec<KeyPair> {
+    anneal_keyed_vigenere_monitored(&Alphabet::standard(), ciphertext, config, &Unmonitored)
+}
+
+/// Steps between progress reports and checks for cancellation.
+const REPORT_STEPS: usize = 1000;
+
+/// `anneal_keyed_vigenere` over the symbols of `base`, reporting its progress through the
+/// steps of every restart and each new best pair, and stopping early when cancelled.
+pub fn anneal_keyed_vigenere_monitored(base: &Alphabet, ciphertext: &str, config: &AnnealConfig, monitor: &dyn Monitor) -> Vec<KeyPair> {
+    let model = NgramModel::english(4).expect("built-in quadgrams");
+    let cipher = base.indices(ciphertext);
+    let letters: Vec<Option<usize>> = base
+        .symbols()
+        .iter()
+        .map(|c| c.is_ascii_uppercase().then(|| (*c as u8 - b'A') as usize))
+        .collect();
+    let mut best: Vec<KeyPair> = Vec::with_capacity(config.keep + 1);
+    if cipher.len() < model.n || config.keep == 0 {
+        return best;
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let scale = cipher.len() as f64 / 100.0;
+    let cooling = if config.steps > 1 {
+        (config.end_temperature / config.start_temperature).powf(1.0 / (config.steps - 1) as f64)
+    } else {
+        1.0
+    };
+
+    let restarts = config.restarts.max(1);
+    let mut reported = f64::NEG_INFINITY;
+    for restart in 0..restarts {
+        let mut state = State::random(&mut rng, base, config);
+        let mut current = score(model, &letters, &state.decrypt(&cipher));
+        let mut temperature = config.start_temperature * scale;
+
+        for step in 0..config.steps {
+            if step % REPORT_STEPS == 0 {
+                if monitor.cancelled() {
+                    return best;
+                }
+                monitor.progress(restart * config.steps + step, restarts * config.steps);
+                if let Some(top) = best.first().filter(|top| top.score > reported) {
+                    reported = top.score;
+                    monitor.best(format!("Key 1: {} Key 2: {} Score: {:.1}\n{}", top.keyword1, top.keyword2, top.score, top.plaintext));
+                }
+            }
+            let previous = state.clone();
+            state.mutate(&mut rng);
+            let candidate = score(model, &letters, &state.decrypt(&cipher));
+            let delta = candidate - current;
+
+            if delta >= 0.0 || rng.gen::<f64>() < (delta / temperature).exp() {
+                current = candidate;
+                keep_best(&mut best, base, &state, &cipher, current, config.keep);
+            } else {
+                state = previous;
+            }
+            temperature *= cooling;
+        }
+    }
+    best
+}
+
+fn keep_best(best: &mut Vec<KeyPair>, base: &Alphabet, state: &State, cipher: &[usize], score: f64, keep: usize) {
+    if best.len() == keep && best.last().is_some_and(|worst| score <= worst.score) {
+        return;
+    }
+    let (keyword1, keyword2) = state.keywords(base);
+    if best.iter().any(|b| b.keyword1 == keyword1 && b.keyword2 == keyword2) {
+        return;
+    }
+    let plaintext = base.text(&state.decrypt(cipher));
+    let position = best.iter().position(|b| score > b.score).unwrap_or(best.len());
+    best.insert(position, KeyPair { keyword1, keyword2, plaintext, score });
+    best.truncate(keep);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toolkit::{vigenere_two_decrypt, vigenere_two_encrypt};
+
+    const PLAINTEXT: &str = "THEREWASNOTHINGTOBESEENOFTHEPASSAGEBEYONDTHEDOORWAYBUTASTHEHOTAIRESCAPEDFROMTHECHAMBER\
+        THECANDLEFLAMEFLICKEREDANDPRESENTLYDETAILSOFTHEROOMWITHINEMERGEDFROMTHEMISTXCANYOUSEEANYTHINGQ";
+
+    #[test]
+    fn reported_keys_reproduce_the_plaintext() {
+        let ciphertext = vigenere_two_encrypt(PLAINTEXT, "KRYPTOS", "ABSCISSA");
+        let config = AnnealConfig { steps: 20000, restarts: 2, ..AnnealConfig::default() };
+        let pairs = anneal_keyed_vigenere(&ciphertext, &config);
+        assert!(!pairs.is_empty());
+        for pair in pairs {
+            assert_eq!(vigenere_two_decrypt(&ciphertext, &pair.keyword1, &pair.keyword2), pair.plaintext, "{} {}", pair.keyword1, pair.keyword2);
+        }
+    }
+}
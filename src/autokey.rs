@@ -0,0 +1,242 @@
+use serde::Serialize;
+
+use crate::{
+    alphabet::Alphabet,
+    analysis::chi_squared_score,
+    ngram::english_fitness,
+    toolkit::{autokey_decrypt_in, Autokey, Family},
+};
+
+#[derive(Serialize)]
+pub struct AutokeyCandidate {
+    pub family: Family,
+    pub mode: Autokey,
+    pub primer: String,
+    pub plaintext: String,
+    pub score: f64,
+}
+
+/// Positions whose plaintext depends on primer letter `r`, in key stream order.
+fn chain(mode: Autokey, r: usize, primer_length: usize, length: usize) -> Vec<usize> {
+    match mode {
+        Autokey::Plaintext => (r..length).step_by(primer_length).collect(),
+        Autokey::Ciphertext => vec![r],
+    }
+}
+
+/// Plaintext indices along a chain when the primer letter is `k`.
+fn decrypt_chain(cipher: &[usize], positions: &[usize], family: Family, mode: Autokey, primer_length: usize, size: usize, k: usize) -> Vec<usize> {
+    let mut k = k;
+    positions
+        .iter()
+        .map(|&i| {
+            if mode == Autokey::Ciphertext && i >= primer_length {
+                k = cipher[i - primer_length];
+            }
+            let p = family.decrypt_index_in(cipher[i], k, size);
+            k = p;
+            p
+        })
+        .collect()
+}
+
+/// Ciphertext-only primer recovery. Each primer letter fixes its own chain of plaintext
+/// letters, so every letter is chosen independently by the chi-squared score of its chain.
+/// Candidates for every primer length up to `max_primer_length` are returned best first
+/// by quadgram fitness of the whole decryption.
+pub fn autokey_solve(
+    ciphertext: &str,
+    family: Family,
+    mode: Autokey,
+    key1: &str,
+    max_primer_length: usize,
+) -> Vec<AutokeyCandidate> {
+    autokey_solve_in(&Alphabet::standard().keyed(key1), ciphertext, family, mode, max_primer_length)
+}
+
+/// `autokey_solve` over an already keyed `alphabet`.
+pub fn autokey_solve_in(alphabet: &Alphabet, ciphertext: &str, family: Family, mode: Autokey, max_primer_length: usize) -> Vec<AutokeyCandidate> {
+    let cipher = alphabet.indices(ciphertext);
+    let size = alphabet.len();
+    let mut candidates = Vec::new();
+
+    for primer_length in 1..=max_primer_length.min(cipher.len()) {
+        let mut primer = String::with_capacity(primer_length);
+        for r in 0..primer_length {
+            let positions = chain(mode, r, primer_length, cipher.len());
+            let best = (0..size)
+                .map(|k| {
+                    let chain_text = alphabet.text(&decrypt_chain(&cipher, &positions, family, mode, primer_length, size, k));
+                    (k, chi_squared_score(&chain_text))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(k, _)| k)
+                .unwrap_or(0);
+            primer.push(alphabet.symbol(best));
+        }
+
+        let plaintext = autokey_decrypt_in(alphabet, ciphertext, family, mode, &primer);
+        let score = english_fitness(&plaintext);
+        candidates.push(AutokeyCandidate { family, mode, primer, plaintext, score });
+    }
+
+    rank(&mut candidates);
+    candidates
+}
+
+/// Primer recovery from known plaintext. `crib` is aligned with the ciphertext as written
+/// and uses `_` for unknown letters, like `K4p`; crib letters opposite spaces or other
+/// characters outside the alphabet are dropped along with them. Every primer length that
+/// is consistent with the crib is returned; primer letters the crib cannot reach are `?`
+/// and unknown plaintext is `_`.
+pub fn autokey_crib(
+    ciphertext: &str,
+    family: Family,
+    mode: Autokey,
+    key1: &str,
+    crib: &str,
+    max_primer_length: usize,
+) -> Vec<AutokeyCandidate> {
+    autokey_crib_in(&Alphabet::standard().keyed(key1), ciphertext, family, mode, crib, max_primer_length)
+}
+
+/// `autokey_crib` over an already keyed `alphabet`.
+pub fn autokey_crib_in(alphabet: &Alphabet, ciphertext: &str, family: Family, mode: Autokey, crib: &str, max_primer_length: usize) -> Vec<AutokeyCandidate> {
+    let cipher = alphabet.indices(ciphertext);
+    let size = alphabet.len();
+    let known: Vec<(usize, usize)> = ciphertext
+        .chars()
+        .zip(crib.chars())
+        .filter(|&(c, _)| alphabet.index(c).is_some())
+        .enumerate()
+        .filter(|&(_, (_, p))| p != '_')
+        .filter_map(|(i, (_, p))| alphabet.index(p).map(|p| (i, p)))
+        .collect();
+    let mut candidates = Vec::new();
+
+    'lengths: for primer_length in 1..=max_primer_length.min(cipher.len()) {
+        let mut primer: Vec<Option<usize>> = vec![None; primer_length];
+        let mut plain: Vec<Option<usize>> = vec![None; cipher.len()];
+
+        for (r, primer_letter) in primer.iter_mut().enumerate() {
+            let positions = chain(mode, r, primer_length, cipher.len());
+            let mut fixed: Option<usize> = None;
+            for (n, &i) in positions.iter().enumerate() {
+                let Some(&(_, p)) = known.iter().find(|&&(j, _)| j == i) else { continue };
+                // Walk the chain back to the primer letter this crib letter implies.
+                let mut k = family.key_index_in(p, cipher[i], size);
+                for &j in positions[..n].iter().rev() {
+                    k = family.key_index_in(k, cipher[j], size);
+                }
+                match fixed {
+                    Some(f) if f != k => continue 'lengths,
+                    _ => fixed = Some(k),
+                }
+            }
+            if let Some(k) = fixed {
+                *primer_letter = Some(k);
+                for (&i, p) in positions.iter().zip(decrypt_chain(&cipher, &positions, family, mode, primer_length, size, k)) {
+                    plain[i] = Some(p);
+                }
+            }
+        }
+
+        if mode == Autokey::Ciphertext {
+            for i in primer_length..cipher.len() {
+                plain[i] = Some(family.decrypt_index_in(cipher[i], cipher[i - primer_length], size));
+            }
+        }
+        if known.iter().any(|&(i, p)| plain[i] != Some(p)) {
+            continue;
+        }
+
+        let primer: String = primer.iter().map(|k| k.map_or('?', |k| alphabet.symbol(k))).collect();
+        let plaintext: String = plain.iter().map(|p| p.map_or('_', |p| alphabet.symbol(p))).collect();
+        let score = known_fitness(&plaintext);
+        candidates.push(AutokeyCandidate { family, mode, primer, plaintext, score });
+    }
+
+    rank(&mut candidates);
+    candidates
+}
+
+/// Quadgram fitness of the recovered stretches of a partial decryption, weighted by length.
+fn known_fitness(plaintext: &str) -> f64 {
+    let (total, grams) = plaintext
+        .split('_')
+        .filter(|run| run.len() >= 4)
+        .fold((0.0, 0), |(total, grams), run| {
+            let n = run.len() - 3;
+            (total + english_fitness(run) * n as f64, grams + n)
+        });
+    if grams == 0 {
+        f64::MIN
+    } else {
+        total / grams as f64
+    }
+}
+
+/// Orders candidates by how much of the primer is determined, then by quadgram fitness.
+pub fn rank(candidates: &mut [AutokeyCandidate]) {
+    candidates.sort_by(|a, b| {
+        let unknown = |c: &AutokeyCandidate| c.primer.matches('?').count() as f64 / c.primer.len() as f64;
+        unknown(a).total_cmp(&unknown(b)).then(b.score.total_cmp(&a.score))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypt::{K1p, K2p},
+        toolkit::autokey_encrypt_in,
+    };
+
+    #[test]
+    fn solve_recovers_a_plaintext_autokey_primer() {
+        let alphabet = Alphabet::standard();
+        for family in Family::ALL {
+            let ciphertext = autokey_encrypt_in(&alphabet, K2p, family, Autokey::Plaintext, "KRYPTOS");
+            let best = &autokey_solve_in(&alphabet, &ciphertext, family, Autokey::Plaintext, 10)[0];
+            assert_eq!(best.primer, "KRYPTOS", "{family:?}");
+            assert_eq!(best.plaintext, K2p, "{family:?}");
+        }
+    }
+
+    #[test]
+    fn solve_decrypts_a_ciphertext_autokey_past_the_primer() {
+        // Each primer letter only decides one plaintext letter, so only the rest is certain.
+        let alphabet = Alphabet::standard();
+        for family in Family::ALL {
+            let ciphertext = autokey_encrypt_in(&alphabet, K2p, family, Autokey::Ciphertext, "KRYPTOS");
+            let best = &autokey_solve_in(&alphabet, &ciphertext, family, Autokey::Ciphertext, 10)[0];
+            assert_eq!(best.primer.len(), 7, "{family:?}");
+            assert_eq!(best.plaintext[7..], K2p[7..], "{family:?}");
+        }
+    }
+
+    #[test]
+    fn crib_recovers_the_primer_in_every_mode_and_family() {
+        let alphabet = Alphabet::standard();
+        let crib = format!("{}{}", &K2p[..12], "_".repeat(K2p.len() - 12));
+        for family in Family::ALL {
+            for mode in Autokey::ALL {
+                let ciphertext = autokey_encrypt_in(&alphabet, K2p, family, mode, "KRYPTOS");
+                let candidates = autokey_crib_in(&alphabet, &ciphertext, family, mode, &crib, 10);
+                let found = candidates.iter().find(|c| c.primer == "KRYPTOS").unwrap_or_else(|| panic!("{family:?} {mode:?}"));
+                assert_eq!(found.plaintext, K2p, "{family:?} {mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn crib_is_aligned_with_the_ciphertext_as_written() {
+        let alphabet = Alphabet::standard();
+        let ciphertext = autokey_encrypt_in(&alphabet, K1p, Family::Vigenere, Autokey::Plaintext, "PALIMPSEST");
+        let spaced = format!("{} {}", &ciphertext[..5], &ciphertext[5..]);
+        let crib = format!("{} {}", &K1p[..5], &K1p[5..15]);
+        let candidates = autokey_crib_in(&alphabet, &spaced, Family::Vigenere, Autokey::Plaintext, &crib, 10);
+        assert_eq!(candidates[0].primer, "PALIMPSEST");
+        assert_eq!(candidates[0].plaintext, K1p);
+    }
+}
@@ -0,0 +1,518 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use itertools::Itertools;
+use serde::Serialize;
+
+use krypton::{
+    alphabet::Alphabet,
+    analysis::{percentage_blocks, try_analyze, try_friedman_key_length, AnalysisResult},
+    anneal::{anneal_keyed_vigenere_monitored, AnnealConfig},
+    autokey::{autokey_crib_in, autokey_solve_in, rank},
+    cipher::{find_cipher, registry},
+    constraints::{solve_constraints, Outcome},
+    crib::crib_drag,
+    decipher::{pretty_grid, try_keyless},
+    error::{KryptonError, Result},
+    export::{analyze_directory, to_csv, to_json, Record},
+    jobs::Unmonitored,
+    key_recovery::recover_keys_in,
+    kullback::{kullback_graph, try_kullback},
+    ngram::{count_ngrams, ngram_table, NgramModel},
+    normalize::try_preserve_format,
+    running_key::{load_corpus, running_key_search_monitored},
+    significance::SignificanceConfig,
+    toolkit::{Autokey, Family},
+    transposition::{columnar_solve, rotating_grid_search},
+};
+
+/// Ciphers, solvers and statistics of the KRYPTON toolkit for scripts and shell pipelines.
+/// Text is read from FILE, or from stdin when FILE is missing or `-`.
+#[derive(Parser)]
+#[command(name = "krypton", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Statistics, Kasiski examination, IoC curve and cipher type guesses for a ciphertext.
+    Analyze(AnalyzeArgs),
+    /// Analyze every file in a directory into one combined report.
+    Batch(BatchArgs),
+    /// Encrypt plaintext with a cipher from the toolkit.
+    Encrypt(CipherArgs),
+    /// Decrypt ciphertext with a cipher from the toolkit.
+    Decrypt(CipherArgs),
+    /// Search for the key of a ciphertext.
+    Solve(SolveArgs),
+    /// Kullback's test: average column IoC at every width, drawn as a bar graph.
+    Kullback(KullbackArgs),
+    /// Count the letter n-grams of a text into a table that the n-gram model loads.
+    Ngrams(NgramArgs),
+}
+
+#[derive(Args)]
+struct Input {
+    /// Text file to read, stdin when missing or `-`.
+    file: Option<String>,
+    #[command(flatten)]
+    alphabet: AlphabetArgs,
+}
+
+impl Input {
+    fn text(&self) -> Result<String> {
+        read_text(self.file.as_deref())
+    }
+
+    fn alphabet(&self) -> Result<Alphabet> {
+        self.alphabet.alphabet()
+    }
+}
+
+#[derive(Args)]
+struct AlphabetArgs {
+    #[arg(short, long, value_enum, default_value_t = AlphabetName::Standard)]
+    alphabet: AlphabetName,
+    /// Symbols of a custom alphabet in order, which replaces `--alphabet`.
+    #[arg(long)]
+    symbols: Option<String>,
+}
+
+impl AlphabetArgs {
+    fn alphabet(&self) -> Result<Alphabet> {
+        match &self.symbols {
+            Some(symbols) => Alphabet::custom(symbols),
+            None => Alphabet::choice(self.alphabet as usize, ""),
+        }
+    }
+}
+
+/// The fixed entries of `ALPHABETS`, in the same order.
+#[derive(Clone, Copy, ValueEnum)]
+enum AlphabetName {
+    /// A to Z.
+    Standard,
+    /// A to Z without J, which is read as I.
+    MergedIj,
+    /// A to Z then 0 to 9.
+    Alphanumeric,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Aligned text for reading in a terminal.
+    Text,
+    Json,
+    /// A heading row and one row per result, for spreadsheets.
+    Csv,
+}
+
+#[derive(Args)]
+struct AnalyzeArgs {
+    #[command(flatten)]
+    input: Input,
+    #[command(flatten)]
+    options: AnalysisOptions,
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    /// Directory whose files are each analyzed as a ciphertext.
+    dir: String,
+    #[command(flatten)]
+    alphabet: AlphabetArgs,
+    #[command(flatten)]
+    options: AnalysisOptions,
+}
+
+#[derive(Args)]
+struct AnalysisOptions {
+    /// Known plaintext file, aligned with the ciphertext.
+    #[arg(short, long)]
+    plaintext: Option<String>,
+    #[arg(short, long, default_value_t = 20)]
+    max_key_length: usize,
+    /// Kasiski factors to leave out, comma separated.
+    #[arg(long, default_value = "1,2,4", value_delimiter = ',')]
+    exclude: Vec<usize>,
+    /// Significance trials, 0 to skip the significance tests.
+    #[arg(long, default_value_t = 100)]
+    trials: usize,
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+    /// Also test significance against periodic encryptions of English.
+    #[arg(long)]
+    english: bool,
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+impl AnalysisOptions {
+    fn significance(&self) -> SignificanceConfig {
+        SignificanceConfig { trials: self.trials, seed: self.seed, english: self.english }
+    }
+}
+
+#[derive(Args)]
+struct CipherArgs {
+    #[command(flatten)]
+    input: Input,
+    /// Cipher name as in the toolkit, such as `keyed-vigenere` or "Quagmire III".
+    #[arg(short, long)]
+    cipher: String,
+    /// Key parameters in the cipher's order, one flag each.
+    #[arg(short, long = "key")]
+    keys: Vec<String>,
+    /// Keep case, spacing and punctuation in the output.
+    #[arg(long)]
+    preserve_format: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Method {
+    /// Periodic key recovery for each family at `--period`; `--key` keys the tableau.
+    Recover,
+    /// Autokey primer recovery, from the known plaintext when given.
+    Autokey,
+    /// Known plaintext constraints on periodic and Quagmire hypotheses.
+    Constraints,
+    /// Cipher alphabets of each key position at `--period`, rebuilt from the known plaintext.
+    Keyless,
+    /// Crib dragging across the ciphertext.
+    Crib,
+    /// Running key search through a corpus directory.
+    RunningKey,
+    /// Columnar transposition hill climbing.
+    Columnar,
+    /// Rotating grid search against the known plaintext.
+    RotatingGrid,
+    /// Simulated annealing of both keyed Vigenere keywords, the second `--key2-length` long.
+    Anneal,
+}
+
+#[derive(Args)]
+struct SolveArgs {
+    #[arg(value_enum)]
+    method: Method,
+    #[command(flatten)]
+    input: Input,
+    /// Known plaintext file, aligned with the ciphertext and `_` where unknown.
+    #[arg(short, long)]
+    plaintext: Option<String>,
+    /// Longest period, primer or grid width the searches try, and the longest period
+    /// considered when estimating `--period`.
+    #[arg(short, long, default_value_t = 20)]
+    max_key_length: usize,
+    /// Period of the key for `recover` and `keyless`. When missing it is the Friedman test's
+    /// estimate for the ciphertext, up to `--max-key-length`.
+    #[arg(long)]
+    period: Option<usize>,
+    /// Keyword of the tableau alphabet.
+    #[arg(short, long, default_value = "")]
+    key: String,
+    /// Crib to drag along the ciphertext.
+    #[arg(long, default_value = "")]
+    crib: String,
+    /// Alphabet keywords tried by the constraints besides straight and unknown alphabets, comma separated.
+    #[arg(long, default_value = "KRYPTOS,ABSCISSA,PALIMPSEST", value_delimiter = ',')]
+    keywords: Vec<String>,
+    /// Directory of key texts for the running key search.
+    #[arg(long)]
+    corpus: Option<String>,
+    /// Candidates to print.
+    #[arg(short, long, default_value_t = 10)]
+    limit: usize,
+    /// Length of the first keyword when annealing.
+    #[arg(long, default_value_t = 7)]
+    key1_length: usize,
+    /// Length of the second keyword when annealing, which is the period. When missing it is
+    /// `--period` or its estimate.
+    #[arg(long)]
+    key2_length: Option<usize>,
+    #[arg(long, default_value_t = 100000)]
+    steps: usize,
+    #[arg(long, default_value_t = 20)]
+    restarts: usize,
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+impl SolveArgs {
+    /// `--period`, or the key length that gives the highest average column IoC.
+    fn period(&self, encrypted: &str) -> Result<usize> {
+        match self.period {
+            Some(period) => Ok(period),
+            None => Ok(try_friedman_key_length(encrypted, &self.input.alphabet()?, self.max_key_length)?.0),
+        }
+    }
+}
+
+#[derive(Args)]
+struct KullbackArgs {
+    #[command(flatten)]
+    input: Input,
+    #[arg(short, long, default_value_t = 60)]
+    max_key_length: usize,
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
+
+#[derive(Args)]
+struct NgramArgs {
+    /// Text file to read, stdin when missing or `-`.
+    file: Option<String>,
+    /// Letters in each n-gram, from 2 to 5.
+    #[arg(short, long, default_value_t = 4)]
+    n: usize,
+    /// Leave out n-grams seen fewer times than this.
+    #[arg(long, default_value_t = 1)]
+    min_count: u64,
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Analyze(args) => analyze(&args),
+        Command::Batch(args) => batch(&args),
+        Command::Encrypt(args) => run_cipher(&args, true),
+        Command::Decrypt(args) => run_cipher(&args, false),
+        Command::Solve(args) => solve(&args),
+        Command::Kullback(args) => kullback(&args),
+        Command::Ngrams(args) => ngrams(&args),
+    };
+    match result {
+        Ok(output) => {
+            // A reader that stops early, like `head`, is not an error.
+            let _ = writeln!(io::stdout(), "{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("krypton: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// The whole of a file, or of stdin for `None` or `-`, without the final line break.
+fn read_text(file: Option<&str>) -> Result<String> {
+    let mut text = String::new();
+    match file {
+        None | Some("-") => {
+            io::stdin().read_to_string(&mut text).map_err(|e| KryptonError::Io(format!("Could not read stdin: {}", e)))?;
+        }
+        Some(path) => {
+            text = fs::read_to_string(path).map_err(|e| KryptonError::Io(format!("Could not read {}: {}", path, e)))?;
+        }
+    }
+    Ok(text.trim_end_matches(['\n', '\r']).to_string())
+}
+
+fn read_plaintext(file: Option<&str>) -> Result<String> {
+    match file {
+        Some(path) => read_text(Some(path)),
+        None => Ok(String::new()),
+    }
+}
+
+fn analyze(args: &AnalyzeArgs) -> Result<String> {
+    let encrypted = args.input.text()?;
+    let options = &args.options;
+    let plaintext = read_plaintext(options.plaintext.as_deref())?;
+    let value = try_analyze(&encrypted, &plaintext, options.max_key_length, &options.exclude, &args.input.alphabet()?, &options.significance())?;
+    match options.format {
+        Format::Text => Ok(analysis_text(&value)),
+        Format::Json => to_json(&value),
+        Format::Csv => to_csv(std::slice::from_ref(&value)),
+    }
+}
+
+fn analysis_text(value: &AnalysisResult) -> String {
+    let mut lines = vec![
+        format!("{:<27}| {}", "FRIEDMAN KEY LENGTH", value.friedman.0),
+        format!("{:<27}| {}", "BEST PHI PERIOD", value.phi.0),
+        format!("{:<27}| {} {}", "KEY ELIMINATION", value.key_elim.0, value.key_elim.2),
+        format!("{:<27}| {:?}", "KASISKI EXAMINATION", value.kasiski.top(4)),
+    ];
+    for (name, statistic) in value.statistics() {
+        lines.push(format!("{:<27}| {}", name, statistic));
+    }
+    for guess in &value.cipher_types {
+        lines.push(format!("{:<27}| {}   | {:.3}", guess.family.to_uppercase(), percentage_blocks(guess.score, 0.0, 1.0), guess.score));
+    }
+    lines.push(String::new());
+    lines.push(format!("{:<8}{:<10}COLUMNS", "PERIOD", "AVERAGE"));
+    for period in &value.ioc_curve {
+        lines.push(format!("{:<8}{:<10.3}{}", period.period, period.average, period.columns.iter().map(|c| format!("{:.2}", c)).join(" ")));
+    }
+    lines.push(String::new());
+    lines.push("SEQUENCE  POSITIONS  DISTANCES".to_string());
+    for repeat in &value.kasiski.repeats {
+        lines.push(format!("{}  {}  {}", repeat.sequence, repeat.positions.iter().join(" "), repeat.distances.iter().join(" ")));
+    }
+    if !value.significance.is_empty() {
+        lines.push(String::new());
+        lines.push(format!("{:<27}{:>10}{:>12}{:>8}{:>12}{:>8}", "STATISTIC", "VALUE", "SHUFFLED Z", "P", "ENGLISH Z", "P"));
+        for test in &value.significance {
+            let mut line = format!("{:<27}{:>10.3}{:>12.2}{:>8.3}", test.statistic, test.value, test.shuffled.z, test.shuffled.p);
+            if let Some(english) = &test.english {
+                line.push_str(&format!("{:>12.2}{:>8.3}", english.z, english.p));
+            }
+            lines.push(line);
+        }
+    }
+    lines.join("\n")
+}
+
+/// One line per file, or the full analyses as JSON or CSV.
+fn batch(args: &BatchArgs) -> Result<String> {
+    let options = &args.options;
+    let plaintext = read_plaintext(options.plaintext.as_deref())?;
+    let entries = analyze_directory(Path::new(&args.dir), &plaintext, options.max_key_length, &options.exclude, &args.alphabet.alphabet()?, &options.significance())?;
+    render(&entries, options.format, |entry| match &entry.analysis {
+        Some(value) => format!(
+            "{} Friedman: {} Phi: {} Kasiski: {:?} Fitness: {:.3} Type: {}",
+            entry.file,
+            value.friedman.0,
+            value.phi.0,
+            value.kasiski.top(4),
+            value.fitness,
+            value.cipher_types.first().map_or("", |guess| guess.family),
+        ),
+        None => format!("{} Error: {}", entry.file, entry.error.as_deref().unwrap_or_default()),
+    })
+}
+
+/// Results as JSON, as CSV, or as text with one `text` entry per result.
+fn render<T: Serialize + Record>(items: &[T], format: Format, text: impl Fn(&T) -> String) -> Result<String> {
+    match format {
+        Format::Text => Ok(items.iter().map(text).join("\n")),
+        Format::Json => to_json(items),
+        Format::Csv => to_csv(items),
+    }
+}
+
+fn run_cipher(args: &CipherArgs, encrypt: bool) -> Result<String> {
+    let cipher = find_cipher(&args.cipher.replace(['-', '_'], " ")).ok_or_else(|| KryptonError::Invalid {
+        input: "Cipher",
+        reason: format!("'{}' is not one of {}", args.cipher, registry().iter().map(|c| c.name()).join(", ")),
+    })?;
+    let keys: Vec<&str> = args.keys.iter().map(String::as_str).collect();
+    let key = cipher.parse_key(&keys)?.with_alphabet(args.input.alphabet()?);
+    let text = args.input.text()?;
+    let apply = |text: &str| if encrypt { cipher.try_encrypt(text, &key) } else { cipher.try_decrypt(text, &key) };
+    if args.preserve_format {
+        try_preserve_format(&text, &key.alphabet, apply)
+    } else {
+        apply(&text)
+    }
+}
+
+fn solve(args: &SolveArgs) -> Result<String> {
+    let encrypted = args.input.text()?;
+    let plaintext = read_plaintext(args.plaintext.as_deref())?;
+    match args.method {
+        Method::Recover => render(&recover_keys_in(&args.input.alphabet()?.keyed(&args.key), &encrypted, args.period(&encrypted)?), args.format, |r| {
+            format!("{} Key: {} Fitness: {:.3}\nConfidence: {}\n{}", r.family.name(), r.key, r.fitness, r.confidence.iter().map(|c| format!("{:.2}", c)).join(" "), r.plaintext)
+        }),
+        Method::Autokey => {
+            let alphabet = args.input.alphabet()?.keyed(&args.key);
+            let crib = plaintext.chars().any(|c| alphabet.index(c).is_some());
+            let mut candidates = Vec::new();
+            for family in Family::ALL {
+                for mode in Autokey::ALL {
+                    if crib {
+                        candidates.extend(autokey_crib_in(&alphabet, &encrypted, family, mode, &plaintext, args.max_key_length));
+                    } else {
+                        candidates.extend(autokey_solve_in(&alphabet, &encrypted, family, mode, args.max_key_length));
+                    }
+                }
+            }
+            rank(&mut candidates);
+            candidates.truncate(args.limit);
+            render(&candidates, args.format, |c| format!("{} {} Primer: {} Score: {:.3}\n{}", c.family.name(), c.mode.name(), c.primer, c.score, c.plaintext))
+        }
+        Method::Constraints => {
+            let keywords: Vec<&str> = args.keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()).collect();
+            let reports = solve_constraints(&encrypted, &plaintext, &args.input.alphabet()?, &keywords, args.max_key_length)?;
+            render(&reports, args.format, |r| match &r.outcome {
+                Outcome::Consistent { key, plain_alphabet, cipher_alphabet, plaintext, checked } => {
+                    format!("{} Period: {} Key: {} Checked: {}\nAlphabets: {} / {}\n{}", r.hypothesis.name(), r.period, key, checked, plain_alphabet, cipher_alphabet, plaintext)
+                }
+                Outcome::Contradicted(c) => format!("{} Period: {} Contradicted: {}", r.hypothesis.name(), r.period, c),
+            })
+        }
+        Method::Keyless => {
+            let report = try_keyless(&encrypted, &plaintext, args.period(&encrypted)?)?;
+            render(std::slice::from_ref(&report), args.format, |r| format!("{}Alphabets: {}\n{}", pretty_grid(&r.table), r.alphabets(), r.decoded))
+        }
+        Method::Crib => {
+            let mut matches = crib_drag(&encrypted, &args.crib, &args.input.alphabet()?, &args.key);
+            matches.truncate(args.limit);
+            render(&matches, args.format, |m| {
+                let tableau = if m.keyword.is_empty() { "STRAIGHT" } else { m.keyword.as_str() };
+                format!(
+                    "Offset: {} {} {} Fragment: {} Period: {} Periodicity: {:.2} Repeat: {} Fitness: {:.2} Score: {:.2}",
+                    m.offset, m.family.name(), tableau, m.fragment, m.period, m.periodicity, m.repeat, m.fitness, m.score,
+                )
+            })
+        }
+        Method::RunningKey => {
+            let dir = args.corpus.as_deref().ok_or(KryptonError::EmptyKey("Corpus Directory"))?;
+            let corpus = load_corpus(Path::new(dir)).map_err(|e| KryptonError::Io(format!("Could not read corpus: {}", e)))?;
+            render(&running_key_search_monitored(&args.input.alphabet()?.keyed(&args.key), &encrypted, &Family::ALL, &corpus, &plaintext, args.limit, &Unmonitored), args.format, |c| {
+                format!("{} @ {} {} Crib: {} Fitness: {:.3}\nKey: {}\n{}", c.source, c.offset, c.family.name(), c.crib_matches, c.fitness, c.key, c.plaintext)
+            })
+        }
+        Method::Columnar => {
+            let mut candidates = columnar_solve(&encrypted, args.max_key_length, args.restarts, args.seed);
+            candidates.truncate(args.limit);
+            render(&candidates, args.format, |c| format!("Width: {} Key: {} Fitness: {:.3}\n{}", c.width, c.key, c.fitness, c.plaintext))
+        }
+        Method::RotatingGrid => render(&rotating_grid_search(&encrypted, &plaintext, args.max_key_length, args.limit), args.format, |c| {
+            format!("Widths: {} {} {} Crib: {} Fitness: {:.3}\n{}", c.width1, c.width2, c.rotation.name(), c.crib_matches, c.fitness, c.plaintext)
+        }),
+        Method::Anneal => {
+            let config = AnnealConfig {
+                keyword1_length: args.key1_length,
+                keyword2_length: match args.key2_length {
+                    Some(length) => length,
+                    None => args.period(&encrypted)?,
+                },
+                steps: args.steps,
+                restarts: args.restarts,
+                seed: args.seed,
+                keep: args.limit,
+                ..AnnealConfig::default()
+            };
+            render(&anneal_keyed_vigenere_monitored(&args.input.alphabet()?, &encrypted, &config, &Unmonitored), args.format, |p| {
+                format!("Key 1: {} Key 2: {} Score: {:.1}\n{}", p.keyword1, p.keyword2, p.score, p.plaintext)
+            })
+        }
+    }
+}
+
+fn kullback(args: &KullbackArgs) -> Result<String> {
+    let widths = try_kullback(&args.input.text()?, &args.input.alphabet()?, args.max_key_length)?;
+    match args.format {
+        Format::Text => Ok(kullback_graph(&widths)),
+        Format::Json => to_json(&widths),
+        Format::Csv => to_csv(&widths),
+    }
+}
+
+/// Builds the model before printing the table, so a bad length or an empty table is
+/// reported instead of written out.
+fn ngrams(args: &NgramArgs) -> Result<String> {
+    let mut counts = count_ngrams(&read_text(args.file.as_deref())?, args.n);
+    counts.retain(|(_, count)| *count >= args.min_count);
+    NgramModel::from_counts(args.n, &counts)?;
+    Ok(ngram_table(&counts, 1).trim_end().to_string())
+}
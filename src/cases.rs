@@ -0,0 +1,181 @@
+use std::{fs, path::Path};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{KryptonError, Result};
+
+/// Directory the GUI loads test cases from, relative to where it is started.
+pub const CASES_DIR: &str = "data/cases";
+
+/// The cases in `data/cases`, compiled in so the browser has them wherever KRYPTON runs.
+const BUILT_IN: [&str; 8] = [
+    include_str!("../data/cases/kryptos_k1.json"),
+    include_str!("../data/cases/kryptos_k2.json"),
+    include_str!("../data/cases/kryptos_k3.json"),
+    include_str!("../data/cases/kryptos_k4.json"),
+    include_str!("../data/cases/caesar_h.json"),
+    include_str!("../data/cases/caesar_k.json"),
+    include_str!("../data/cases/simple_vigenere.json"),
+    include_str!("../data/cases/simple_beaufort.json"),
+];
+
+/// One ciphertext to practise on or test a solver against, read from a JSON file such as
+/// `data/cases/kryptos_k1.json`. Only the name, cipher and ciphertext are required.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    /// A cipher name as in `registry()`, or a type such as `Unknown` for unsolved texts.
+    pub cipher: String,
+    pub ciphertext: String,
+    /// Known plaintext aligned with the ciphertext, `_` where unknown, like `K4p`.
+    #[serde(default)]
+    pub plaintext: String,
+    /// Key parameters in the cipher's order.
+    #[serde(default)]
+    pub key: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl TestCase {
+    pub fn parse(json: &str) -> Result<TestCase> {
+        serde_json::from_str(json).map_err(|e| KryptonError::Invalid { input: "Test Case", reason: e.to_string() })
+    }
+}
+
+/// The compiled-in cases. They are checked by the tests, so a malformed one is a bug.
+pub fn built_in_cases() -> Vec<TestCase> {
+    BUILT_IN.iter().map(|json| TestCase::parse(json).expect("built-in test case")).collect()
+}
+
+/// The cases read from a directory, and an error for each file that could not be read as one.
+#[derive(Default)]
+pub struct LoadedCases {
+    pub cases: Vec<TestCase>,
+    pub errors: Vec<KryptonError>,
+}
+
+impl LoadedCases {
+    /// One error covering every file that could not be loaded, if any.
+    pub fn error(&self) -> Option<KryptonError> {
+        match &self.errors[..] {
+            [] => None,
+            [error] => Some(error.clone()),
+            errors => Some(KryptonError::Invalid {
+                input: "Test Cases",
+                reason: format!("{} files could not be loaded: {}", errors.len(), errors.iter().join("; ")),
+            }),
+        }
+    }
+}
+
+/// Every `.json` file in `dir` as a test case, in file name order. A file that cannot be
+/// read or parsed is reported in `errors` and the rest still load; only a directory that
+/// cannot be listed fails.
+pub fn load_cases(dir: &Path) -> Result<LoadedCases> {
+    let read_error = |e: std::io::Error| KryptonError::Io(format!("Could not read {}: {}", dir.display(), e));
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        if path.is_file() && path.extension().is_some_and(|extension| extension == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut loaded = LoadedCases::default();
+    for path in paths {
+        let case = fs::read_to_string(&path)
+            .map_err(|e| KryptonError::Io(format!("Could not read {}: {}", path.display(), e)))
+            .and_then(|json| {
+                serde_json::from_str(&json).map_err(|e| KryptonError::Invalid { input: "Test Case", reason: format!("{}: {}", path.display(), e) })
+            });
+        match case {
+            Ok(case) => loaded.cases.push(case),
+            Err(e) => loaded.errors.push(e),
+        }
+    }
+    Ok(loaded)
+}
+
+/// The built-in cases followed by those in `dir`, where a case of the same name replaces the
+/// built-in one so it can be edited without recompiling. A missing directory adds nothing,
+/// and the errors of files that could not be loaded are passed on.
+pub fn all_cases(dir: &Path) -> LoadedCases {
+    let mut cases = built_in_cases();
+    if !dir.is_dir() {
+        return LoadedCases { cases, errors: Vec::new() };
+    }
+    let loaded = match load_cases(dir) {
+        Ok(loaded) => loaded,
+        Err(e) => LoadedCases { cases: Vec::new(), errors: vec![e] },
+    };
+    for case in loaded.cases {
+        match cases.iter_mut().find(|c| c.name == case.name) {
+            Some(existing) => *existing = case,
+            None => cases.push(case),
+        }
+    }
+    LoadedCases { cases, errors: loaded.errors }
+}
+
+/// The distinct cipher types of `cases`, sorted, for filtering.
+pub fn cipher_types(cases: &[TestCase]) -> Vec<String> {
+    let mut types: Vec<String> = cases.iter().map(|case| case.cipher.clone()).collect();
+    types.sort();
+    types.dedup();
+    types
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh empty directory under the system temp directory.
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("krypton-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn every_built_in_case_parses() {
+        for json in BUILT_IN {
+            TestCase::parse(json).unwrap();
+        }
+        assert_eq!(built_in_cases().len(), BUILT_IN.len());
+    }
+
+    #[test]
+    fn parse_fills_in_optional_fields() {
+        let case = TestCase::parse(r#"{"name": "Mine", "cipher": "Vigenere", "ciphertext": "RIJVS"}"#).unwrap();
+        assert_eq!((case.plaintext.as_str(), case.key.len(), case.notes.as_str()), ("", 0, ""));
+        assert!(TestCase::parse(r#"{"name": "Mine", "cipher": "Vigenere"}"#).is_err());
+    }
+
+    #[test]
+    fn a_bad_file_does_not_hide_the_good_ones() {
+        let dir = temp_dir("cases");
+        fs::write(dir.join("a.json"), r#"{"name": "Kryptos Section 1", "cipher": "Keyed Vigenere", "ciphertext": "EMUFPHZ"}"#).unwrap();
+        fs::write(dir.join("b.json"), "{ not json").unwrap();
+        fs::write(dir.join("c.json"), r#"{"name": "Extra", "cipher": "Unknown", "ciphertext": "ABC"}"#).unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        let loaded = all_cases(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.errors.len(), 1);
+        assert!(loaded.error().unwrap().to_string().contains("b.json"));
+        assert_eq!(loaded.cases.len(), BUILT_IN.len() + 1);
+        assert_eq!(loaded.cases.iter().find(|c| c.name == "Kryptos Section 1").unwrap().ciphertext, "EMUFPHZ");
+        assert_eq!(loaded.cases.last().unwrap().name, "Extra");
+    }
+
+    #[test]
+    fn cipher_types_are_sorted_and_distinct() {
+        let case = |cipher: &str| TestCase { name: cipher.to_string(), cipher: cipher.to_string(), ciphertext: String::new(), plaintext: String::new(), key: Vec::new(), notes: String::new() };
+        let cases = [case("Vigenere"), case("Unknown"), case("Beaufort"), case("Vigenere")];
+        assert_eq!(cipher_types(&cases), ["Beaufort", "Unknown", "Vigenere"]);
+    }
+}
@@ -0,0 +1,679 @@
+use crate::{alphabet::Alphabet, analysis::aster_score, decipher::new_decode, error::{check_range, require_text, KryptonError, Result}, jobs::{Monitor, Unmonitored}, normalize::preserve_format, toolkit::*};
+
+/// What kind of value a cipher parameter expects.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamKind {
+    Keyword,
+    Number { min: usize, max: usize },
+    Choice(&'static [&'static str]),
+}
+
+/// Describes one key parameter so front ends can build their inputs from it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: ParamKind,
+    /// A keyword whose letters repeat with the period, as searched by `bullshark` and
+    /// lined up against the plaintext by key position.
+    pub periodic: bool,
+}
+
+impl Param {
+    pub fn keyword(name: &'static str, description: &'static str) -> Self {
+        Param { name, description, kind: ParamKind::Keyword, periodic: false }
+    }
+
+    pub fn period_keyword(name: &'static str, description: &'static str) -> Self {
+        Param { name, description, kind: ParamKind::Keyword, periodic: true }
+    }
+
+    pub fn number(name: &'static str, description: &'static str, min: usize, max: usize) -> Self {
+        Param { name, description, kind: ParamKind::Number { min, max }, periodic: false }
+    }
+
+    pub fn choice(name: &'static str, description: &'static str, options: &'static [&'static str]) -> Self {
+        Param { name, description, kind: ParamKind::Choice(options), periodic: false }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum KeyValue {
+    Keyword(String),
+    Number(usize),
+    Choice(usize),
+}
+
+/// A parsed key, one value per parameter of the cipher it was parsed for, and the
+/// alphabet the cipher works over (A to Z unless set).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Key {
+    pub values: Vec<KeyValue>,
+    pub alphabet: Alphabet,
+}
+
+impl Key {
+    pub fn new(values: Vec<KeyValue>) -> Self {
+        Key { values, alphabet: Alphabet::standard() }
+    }
+
+    pub fn with_alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    /// Parses raw text inputs against a parameter list. Missing inputs are treated as empty.
+    pub fn parse(params: &[Param], inputs: &[&str]) -> Result<Key> {
+        let mut values = Vec::with_capacity(params.len());
+        for (i, param) in params.iter().enumerate() {
+            let input = inputs.get(i).copied().unwrap_or("").trim();
+            let value = match &param.kind {
+                ParamKind::Keyword => KeyValue::Keyword(
+                    input.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase(),
+                ),
+                ParamKind::Number { min, max } => {
+                    let n = if input.is_empty() {
+                        *min
+                    } else {
+                        input.parse::<usize>().map_err(|_| KryptonError::Invalid { input: param.name, reason: format!("'{}' is not a number", input) })?
+                    };
+                    check_range(param.name, n, *min, *max)?;
+                    KeyValue::Number(n)
+                }
+                ParamKind::Choice(options) => {
+                    let index = if input.is_empty() {
+                        Some(0)
+                    } else {
+                        options.iter().position(|o| o.eq_ignore_ascii_case(input))
+                    };
+                    match index {
+                        Some(index) => KeyValue::Choice(index),
+                        None => return Err(KryptonError::Invalid { input: param.name, reason: format!("expected one of {}", options.join(", ")) }),
+                    }
+                }
+            };
+            values.push(value);
+        }
+        Ok(Key::new(values))
+    }
+
+    pub fn keyword(&self, index: usize) -> &str {
+        match self.values.get(index) {
+            Some(KeyValue::Keyword(k)) => k,
+            _ => "",
+        }
+    }
+
+    pub fn number(&self, index: usize) -> usize {
+        match self.values.get(index) {
+            Some(KeyValue::Number(n)) => *n,
+            _ => 0,
+        }
+    }
+
+    pub fn choice(&self, index: usize) -> usize {
+        match self.values.get(index) {
+            Some(KeyValue::Choice(c)) => *c,
+            _ => 0,
+        }
+    }
+}
+
+/// Common interface for every cipher in the toolkit.
+pub trait Cipher {
+    fn name(&self) -> &'static str;
+    fn params(&self) -> Vec<Param>;
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String;
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String;
+
+    fn parse_key(&self, inputs: &[&str]) -> Result<Key> {
+        Key::parse(&self.params(), inputs)
+    }
+
+    /// Index of the periodic keyword, if the cipher has one.
+    fn period_slot(&self) -> Option<usize> {
+        self.params().iter().position(|p| p.periodic)
+    }
+
+    /// The periodic keyword must have a symbol of the key's alphabet, or there is no key to
+    /// repeat.
+    fn check_key(&self, key: &Key) -> Result<()> {
+        let Some(slot) = self.period_slot() else { return Ok(()) };
+        if key.keyword(slot).chars().any(|c| key.alphabet.contains(c)) {
+            return Ok(());
+        }
+        Err(KryptonError::Invalid { input: self.params()[slot].name, reason: "needs at least one symbol of the alphabet".to_string() })
+    }
+
+    /// `encrypt`, or an error when the plaintext has nothing in the key's alphabet or the
+    /// periodic keyword is empty.
+    fn try_encrypt(&self, plaintext: &str, key: &Key) -> Result<String> {
+        require_text("Plaintext", plaintext, &key.alphabet)?;
+        self.check_key(key)?;
+        Ok(self.encrypt(plaintext, key))
+    }
+
+    fn try_decrypt(&self, ciphertext: &str, key: &Key) -> Result<String> {
+        require_text("Encrypted", ciphertext, &key.alphabet)?;
+        self.check_key(key)?;
+        Ok(self.decrypt(ciphertext, key))
+    }
+}
+
+/// Every cipher the toolkit knows about, in display order.
+pub fn registry() -> Vec<Box<dyn Cipher>> {
+    vec![
+        Box::new(Vigenere),
+        Box::new(FormatVigenere),
+        Box::new(KeyedVigenere),
+        Box::new(PeriodicCipher::beaufort(false)),
+        Box::new(PeriodicCipher::beaufort(true)),
+        Box::new(PeriodicCipher::variant_beaufort(false)),
+        Box::new(PeriodicCipher::variant_beaufort(true)),
+        Box::new(AutokeyCipher(Autokey::Plaintext)),
+        Box::new(AutokeyCipher(Autokey::Ciphertext)),
+        Box::new(RunningKeyCipher),
+        Box::new(QuagmireCipher(Quagmire::I)),
+        Box::new(QuagmireCipher(Quagmire::II)),
+        Box::new(QuagmireCipher(Quagmire::III)),
+        Box::new(QuagmireCipher(Quagmire::IV)),
+        Box::new(PeriodicSubstitution),
+        Box::new(ColumnarTransposition),
+        Box::new(RotatingGrid),
+    ]
+}
+
+pub fn find_cipher(name: &str) -> Option<Box<dyn Cipher>> {
+    registry().into_iter().find(|c| c.name().eq_ignore_ascii_case(name))
+}
+
+/// Coordinate ascent over one keyword of `key`, two passes of every letter at every
+/// position, scored by `aster_score` against the known plaintext.
+pub fn bullshark(
+    cipher: &dyn Cipher,
+    key: &Key,
+    slot: usize,
+    encrypted_text: &str,
+    plaintext: &str,
+    key_length: usize,
+) -> (Key, f64, String) {
+    bullshark_monitored(cipher, key, slot, encrypted_text, plaintext, key_length, &Unmonitored)
+}
+
+/// `bullshark` reporting each key position as it is done with the best keyword so far,
+/// and returning that keyword when cancelled.
+pub fn bullshark_monitored(
+    cipher: &dyn Cipher,
+    key: &Key,
+    slot: usize,
+    encrypted_text: &str,
+    plaintext: &str,
+    key_length: usize,
+    monitor: &dyn Monitor,
+) -> (Key, f64, String) {
+    let mut best_score = 0.0;
+    let mut best_decrypted = String::new();
+    let mut keyword: Vec<char> = vec![key.alphabet.symbol(0); key_length];
+    let mut key = key.clone();
+    if key.values.len() <= slot {
+        key.values.resize(slot + 1, KeyValue::Keyword(String::new()));
+    }
+
+    'passes: for pass in 0..2 {
+        for i in 0..key_length {
+            if monitor.cancelled() {
+                break 'passes;
+            }
+            monitor.progress(pass * key_length + i, 2 * key_length);
+            let mut best_char = keyword[i];
+
+            for &index in key.alphabet.clone().symbols() {
+                keyword[i] = index;
+                key.values[slot] = KeyValue::Keyword(keyword.iter().collect());
+                let decrypted = cipher.decrypt(encrypted_text, &key);
+                let score = aster_score(plaintext, &decrypted);
+
+                if score > best_score {
+                    best_score = score;
+                    best_decrypted = decrypted;
+                    best_char = index;
+                }
+            }
+
+            keyword[i] = best_char;
+            let keyword: String = keyword.iter().collect();
+            monitor.best(format!("BestScore: {}\nBest Keyword: {}\nDecrypted: {}", best_score, keyword, best_decrypted));
+        }
+    }
+    key.values[slot] = KeyValue::Keyword(keyword.iter().collect());
+
+    (key, best_score, best_decrypted)
+}
+
+pub struct Vigenere;
+
+impl Cipher for Vigenere {
+    fn name(&self) -> &'static str {
+        "Vigenere"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::period_keyword("Key", "Repeating keyword")]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        periodic_encrypt_in(&key.alphabet, plaintext, Family::Vigenere, key.keyword(0))
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        periodic_decrypt_in(&key.alphabet, ciphertext, Family::Vigenere, key.keyword(0))
+    }
+}
+
+/// Vigenere that keeps case, spacing and punctuation of the input.
+pub struct FormatVigenere;
+
+impl Cipher for FormatVigenere {
+    fn name(&self) -> &'static str {
+        "Vigenere (Keep Format)"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::period_keyword("Key", "Repeating keyword")]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        keep_format(plaintext, key, false)
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        keep_format(ciphertext, key, true)
+    }
+}
+
+/// Vigenere over the symbols of `key.alphabet` with everything else left in place. A key
+/// with no symbols of the alphabet leaves the text unchanged.
+fn keep_format(text: &str, key: &Key, decrypt: bool) -> String {
+    let (alphabet, keyword) = (&key.alphabet, key.keyword(0));
+    if alphabet.indices(keyword).is_empty() {
+        return text.to_string();
+    }
+    preserve_format(text, alphabet, |symbols| {
+        if decrypt {
+            periodic_decrypt_in(alphabet, symbols, Family::Vigenere, keyword)
+        } else {
+            periodic_encrypt_in(alphabet, symbols, Family::Vigenere, keyword)
+        }
+    })
+}
+
+pub struct KeyedVigenere;
+
+impl Cipher for KeyedVigenere {
+    fn name(&self) -> &'static str {
+        "Keyed Vigenere"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::keyword("Alphabet Key", "Keyword that mixes the tableau alphabet"),
+            Param::period_keyword("Period Key", "Keyword that selects the tableau rows"),
+        ]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        vigenere_two_encrypt_in(&key.alphabet, plaintext, key.keyword(0), key.keyword(1))
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        vigenere_two_decrypt_in(&key.alphabet, ciphertext, key.keyword(0), key.keyword(1))
+    }
+}
+
+/// Beaufort or variant Beaufort on a straight or keyed alphabet. Vigenere is `Vigenere` and
+/// `KeyedVigenere`, so the constructors only make the other two families.
+pub struct PeriodicCipher {
+    variant: Family,
+    keyed: bool,
+}
+
+impl PeriodicCipher {
+    pub fn beaufort(keyed: bool) -> Self {
+        PeriodicCipher { variant: Family::Beaufort, keyed }
+    }
+
+    pub fn variant_beaufort(keyed: bool) -> Self {
+        PeriodicCipher { variant: Family::VariantBeaufort, keyed }
+    }
+
+    fn keys<'a>(&self, key: &'a Key) -> (&'a str, &'a str) {
+        if self.keyed {
+            (key.keyword(0), key.keyword(1))
+        } else {
+            ("", key.keyword(0))
+        }
+    }
+}
+
+impl Cipher for PeriodicCipher {
+    fn name(&self) -> &'static str {
+        match (self.variant, self.keyed) {
+            (Family::Vigenere, _) => unreachable!("Vigenere has its own ciphers"),
+            (Family::Beaufort, false) => "Beaufort",
+            (Family::Beaufort, true) => "Keyed Beaufort",
+            (Family::VariantBeaufort, false) => "Variant Beaufort",
+            (Family::VariantBeaufort, true) => "Keyed Variant Beaufort",
+        }
+    }
+
+    fn params(&self) -> Vec<Param> {
+        if self.keyed {
+            vec![
+                Param::keyword("Alphabet Key", "Keyword that mixes the tableau alphabet"),
+                Param::period_keyword("Period Key", "Repeating keyword"),
+            ]
+        } else {
+            vec![Param::period_keyword("Key", "Repeating keyword")]
+        }
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        let (key1, key2) = self.keys(key);
+        periodic_encrypt_in(&key.alphabet.keyed(key1), plaintext, self.variant, key2)
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        let (key1, key2) = self.keys(key);
+        periodic_decrypt_in(&key.alphabet.keyed(key1), ciphertext, self.variant, key2)
+    }
+}
+
+pub struct AutokeyCipher(pub Autokey);
+
+impl Cipher for AutokeyCipher {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::keyword("Alphabet Key", "Keyword that mixes the tableau alphabet"),
+            Param::period_keyword("Primer", "Keyword that starts the key stream"),
+            Param::choice("Family", "Tableau arithmetic", &Family::NAMES),
+        ]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        autokey_encrypt_in(&key.alphabet.keyed(key.keyword(0)), plaintext, Family::ALL[key.choice(2)], self.0, key.keyword(1))
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        autokey_decrypt_in(&key.alphabet.keyed(key.keyword(0)), ciphertext, Family::ALL[key.choice(2)], self.0, key.keyword(1))
+    }
+}
+
+pub struct RunningKeyCipher;
+
+impl Cipher for RunningKeyCipher {
+    fn name(&self) -> &'static str {
+        "Running Key"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::keyword("Alphabet Key", "Keyword that mixes the tableau alphabet"),
+            Param::keyword("Key Text", "Passage of text used as the key"),
+            Param::number("Offset", "Letters of the key text to skip", 0, usize::MAX),
+            Param::choice("Family", "Tableau arithmetic", &Family::NAMES),
+        ]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        let alphabet = key.alphabet.keyed(key.keyword(0));
+        running_key_encrypt_in(&alphabet, plaintext, Family::ALL[key.choice(3)], key.keyword(1), key.number(2))
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        let alphabet = key.alphabet.keyed(key.keyword(0));
+        running_key_decrypt_in(&alphabet, ciphertext, Family::ALL[key.choice(3)], key.keyword(1), key.number(2))
+    }
+}
+
+pub struct QuagmireCipher(pub Quagmire);
+
+impl QuagmireCipher {
+    /// Splits a key into (plaintext key, ciphertext key, indicator, indicator position).
+    fn keys<'a>(&self, key: &'a Key) -> (&'a str, &'a str, &'a str, &'a str) {
+        match self.0 {
+            Quagmire::I | Quagmire::III => (key.keyword(0), "", key.keyword(1), key.keyword(2)),
+            Quagmire::II => ("", key.keyword(0), key.keyword(1), key.keyword(2)),
+            Quagmire::IV => (key.keyword(0), key.keyword(1), key.keyword(2), key.keyword(3)),
+        }
+    }
+}
+
+impl Cipher for QuagmireCipher {
+    fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn params(&self) -> Vec<Param> {
+        let mut params = match self.0 {
+            Quagmire::I => vec![Param::keyword("Plaintext Key", "Keyword for the plaintext alphabet")],
+            Quagmire::II => vec![Param::keyword("Ciphertext Key", "Keyword for the ciphertext alphabet")],
+            Quagmire::III => vec![Param::keyword("Alphabet Key", "Keyword for both alphabets")],
+            Quagmire::IV => vec![
+                Param::keyword("Plaintext Key", "Keyword for the plaintext alphabet"),
+                Param::keyword("Ciphertext Key", "Keyword for the ciphertext alphabet"),
+            ],
+        };
+        params.push(Param::period_keyword("Indicator Key", "Letters that select the tableau rows"));
+        params.push(Param::keyword("Indicator Position", "Plaintext letter the indicator sits under"));
+        params
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        quagmire_encrypt_in(&key.alphabet, plaintext, self.0, self.keys(key))
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        quagmire_decrypt_in(&key.alphabet, ciphertext, self.0, self.keys(key))
+    }
+}
+
+/// One cipher alphabet per position, as rebuilt by `keyless`. Unknown letters are `_`.
+pub struct PeriodicSubstitution;
+
+impl PeriodicSubstitution {
+    fn grid(key: &Key) -> Vec<Vec<char>> {
+        let size = key.alphabet.len();
+        let mut grid = vec![key.alphabet.symbols().to_vec()];
+        for row in key.keyword(0).split(',').filter(|r| !r.is_empty()) {
+            let mut row: Vec<char> = row.chars().take(size).collect();
+            row.resize(size, '_');
+            grid.push(row);
+        }
+        if grid.len() == 1 {
+            grid.push(grid[0].clone());
+        }
+        grid
+    }
+}
+
+impl Cipher for PeriodicSubstitution {
+    fn name(&self) -> &'static str {
+        "Periodic Substitution"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::keyword("Alphabets", "Comma separated cipher alphabets, one per position")]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        let grid = Self::grid(key);
+        plaintext
+            .to_uppercase()
+            .chars()
+            .enumerate()
+            .map(|(index, c)| {
+                let row = &grid[(index % (grid.len() - 1)) + 1];
+                grid[0].iter().position(|&p| p == c).map(|p| row[p]).unwrap_or('_')
+            })
+            .collect()
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        new_decode(&Self::grid(key), ciphertext)
+    }
+}
+
+/// Keyed columnar transposition on complete or irregular grids.
+pub struct ColumnarTransposition;
+
+impl Cipher for ColumnarTransposition {
+    fn name(&self) -> &'static str {
+        "Columnar Transposition"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![Param::keyword("Column Key", "Keyword or comma separated column numbers")]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        columnar_encrypt(plaintext, &column_order(key.keyword(0)))
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        columnar_decrypt(ciphertext, &column_order(key.keyword(0)))
+    }
+}
+
+/// K3 style double rotation. `crypt::K3` decrypts at widths 24 and 8, clockwise.
+pub struct RotatingGrid;
+
+impl RotatingGrid {
+    fn keys(key: &Key) -> (usize, usize, Rotation) {
+        (key.number(0), key.number(1), Rotation::ALL[key.choice(2)])
+    }
+}
+
+impl Cipher for RotatingGrid {
+    fn name(&self) -> &'static str {
+        "Rotating Grid"
+    }
+
+    fn params(&self) -> Vec<Param> {
+        vec![
+            Param::number("First Width", "Columns of the grid the ciphertext is written into", 1, usize::MAX),
+            Param::number("Second Width", "Columns of the grid after the first rotation", 1, usize::MAX),
+            Param::choice("Rotation", "Direction the grid is turned", &["Clockwise", "Counterclockwise"]),
+        ]
+    }
+
+    fn encrypt(&self, plaintext: &str, key: &Key) -> String {
+        let (width1, width2, rotation) = Self::keys(key);
+        rotating_grid_encrypt(plaintext, width1, width2, rotation)
+    }
+
+    fn decrypt(&self, ciphertext: &str, key: &Key) -> String {
+        let (width1, width2, rotation) = Self::keys(key);
+        rotating_grid_decrypt(ciphertext, width1, width2, rotation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::{K1, K1p, K2, K2p, K3, K3p};
+
+    const PLAINTEXT: &str = "BETWEENSUBTLESHADINGANDTHEABSENCEOFLIGHTLIESTHENUANCEOFIQLUSION";
+
+    /// Key inputs for every cipher in the registry.
+    fn inputs(cipher: &dyn Cipher) -> Vec<&'static str> {
+        match cipher.name() {
+            "Running Key" => vec!["KRYPTOS", "ITWASTOTALLYINVISIBLEHOWSTHATPOSSIBLETHEYUSEDTHEEARTHSMAGNETICFIELDX", "3", "Beaufort"],
+            "Periodic Substitution" => vec!["KRYPTOSABCDEFGHIJLMNQUVWXZ,PALIMSETBCDFGHJKNOQRUVWXYZ"],
+            "Rotating Grid" => vec!["7", "4", "Counterclockwise"],
+            _ => cipher.params().iter().map(|param| match param.kind {
+                ParamKind::Keyword if param.periodic => "ABSCISSA",
+                ParamKind::Keyword => "KRYPTOS",
+                ParamKind::Number { .. } => "5",
+                ParamKind::Choice(options) => options[options.len() - 1],
+            }).collect(),
+        }
+    }
+
+    #[test]
+    fn every_cipher_round_trips() {
+        for cipher in registry() {
+            let key = cipher.parse_key(&inputs(cipher.as_ref())).unwrap();
+            let encrypted = cipher.encrypt(PLAINTEXT, &key);
+            assert_ne!(encrypted, PLAINTEXT, "{}", cipher.name());
+            assert_eq!(cipher.decrypt(&encrypted, &key), PLAINTEXT, "{}", cipher.name());
+        }
+    }
+
+    #[test]
+    fn cipher_names_are_distinct() {
+        let names: Vec<&str> = registry().iter().map(|cipher| cipher.name()).collect();
+        for name in &names {
+            assert_eq!(names.iter().filter(|other| other.eq_ignore_ascii_case(name)).count(), 1, "{}", name);
+            assert_eq!(find_cipher(name).unwrap().name(), *name);
+        }
+    }
+
+    #[test]
+    fn an_empty_period_keyword_is_rejected() {
+        let key = Vigenere.parse_key(&[" "]).unwrap();
+        assert!(matches!(Vigenere.try_encrypt("HELLO", &key), Err(KryptonError::Invalid { input: "Key", .. })));
+        assert!(Vigenere.try_decrypt("HELLO", &key).is_err());
+        let key = KeyedVigenere.parse_key(&["KRYPTOS", "ABSCISSA"]).unwrap();
+        assert_eq!(KeyedVigenere.try_decrypt(K2, &key).unwrap(), KeyedVigenere.decrypt(K2, &key));
+        let key = ColumnarTransposition.parse_key(&["KRYPTOS"]).unwrap();
+        assert!(ColumnarTransposition.try_encrypt("HELLO", &key).is_ok());
+    }
+
+    #[test]
+    fn kryptos_keys_decrypt_k1_to_k3() {
+        let keyed = find_cipher("Keyed Vigenere").unwrap();
+        let key = keyed.parse_key(&["KRYPTOS", "PALIMPSEST"]).unwrap();
+        assert_eq!(keyed.decrypt(K1, &key), K1p);
+        let key = keyed.parse_key(&["KRYPTOS", "ABSCISSA"]).unwrap();
+        // The sculpture's K2 has the X after SOMEWHERE that `K2p` drops, and ends in IDBYROWS.
+        let decrypted = keyed.decrypt(K2, &key);
+        let split = K2p.find("WHOKNOWS").unwrap();
+        assert_eq!(decrypted[..split], K2p[..split]);
+        assert!(decrypted.ends_with("WESTIDBYROWS"), "{decrypted}");
+        let key = RotatingGrid.parse_key(&["24", "8", "Clockwise"]).unwrap();
+        assert_eq!(RotatingGrid.decrypt(K3, &key), K3p);
+    }
+
+    #[test]
+    fn bullshark_searches_the_quagmire_indicator() {
+        for quagmire in Quagmire::ALL {
+            let cipher = QuagmireCipher(quagmire);
+            let inputs: &[&str] = if quagmire == Quagmire::IV { &["KRYPTOS", "PALIMPSEST", "CODE", "A"] } else { &["KRYPTOS", "CODE", "A"] };
+            let key = cipher.parse_key(inputs).unwrap();
+            let ciphertext = cipher.encrypt(PLAINTEXT, &key);
+            let slot = cipher.period_slot().unwrap();
+            assert_eq!(cipher.params()[slot].name, "Indicator Key");
+
+            let mut start = key.clone();
+            start.values[slot] = KeyValue::Keyword(String::new());
+            let (found, _, decrypted) = bullshark(&cipher, &start, slot, &ciphertext, PLAINTEXT, 4);
+            assert_eq!(found.keyword(slot), "CODE", "{}", cipher.name());
+            assert_eq!(decrypted, PLAINTEXT, "{}", cipher.name());
+        }
+    }
+
+    #[test]
+    fn format_vigenere_uses_the_key_alphabet() {
+        let key = FormatVigenere.parse_key(&["KEY"]).unwrap();
+        assert_eq!(FormatVigenere.encrypt("Attack at dawn!", &key), crate::vigenere::vigenere("Attack at dawn!", "KEY"));
+
+        let key = key.with_alphabet(Alphabet::custom("ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789").unwrap());
+        let encrypted = FormatVigenere.encrypt("MEET AT 9PM, DOCK 4.", &key);
+        assert_eq!(encrypted, "WI23 EH JTA, NS0U 8.");
+        assert_eq!(FormatVigenere.decrypt(&encrypted, &key), "MEET AT 9PM, DOCK 4.");
+    }
+}
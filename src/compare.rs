@@ -0,0 +1,143 @@
+use serde::Serialize;
+
+/// How one position of a decryption compares with the reference plaintext.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Agreement {
+    Match,
+    Mismatch,
+    /// Either side is `_`, as in the unknown stretches of `K4p`.
+    Unknown,
+    /// Past the end of the shorter text.
+    Missing,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CharDiff {
+    pub output: Option<char>,
+    pub plaintext: Option<char>,
+    pub agreement: Agreement,
+}
+
+/// Mismatches among the positions one key symbol encrypts.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct KeyColumn {
+    /// Position in the key, from 0.
+    pub position: usize,
+    pub mismatches: usize,
+    /// Positions where both texts have a known symbol.
+    pub compared: usize,
+}
+
+impl KeyColumn {
+    pub fn rate(&self) -> f64 {
+        if self.compared == 0 {
+            0.0
+        } else {
+            self.mismatches as f64 / self.compared as f64
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Comparison {
+    pub chars: Vec<CharDiff>,
+    pub matches: usize,
+    pub mismatches: usize,
+    pub unknown: usize,
+    pub missing: usize,
+    pub columns: Vec<KeyColumn>,
+    /// Key positions whose mismatches stand out from the rest, worst first.
+    pub suspects: Vec<usize>,
+}
+
+/// Share of a column's symbols that must be wrong, and how many times the rate of the other
+/// columns it must be, before its key symbol is suspected. A wrong key letter in a periodic
+/// cipher spoils nearly every symbol of its column and none elsewhere; misspellings such as
+/// K2's UNDERGRUUND fall in one column at a time and never reach it.
+const SUSPECT_RATE: f64 = 0.5;
+const SUSPECT_RATIO: f64 = 2.0;
+
+/// Aligns `output` with `plaintext` symbol by symbol, ignoring whitespace in both, and
+/// groups the mismatches by position modulo `key_length`.
+pub fn compare(output: &str, plaintext: &str, key_length: usize) -> Comparison {
+    let output: Vec<char> = output.chars().filter(|c| !c.is_whitespace()).collect();
+    let plaintext: Vec<char> = plaintext.chars().filter(|c| !c.is_whitespace()).collect();
+    let key_length = key_length.max(1);
+    let mut columns: Vec<KeyColumn> = (0..key_length).map(|position| KeyColumn { position, mismatches: 0, compared: 0 }).collect();
+
+    let chars: Vec<CharDiff> = (0..output.len().max(plaintext.len()))
+        .map(|i| {
+            let (o, p) = (output.get(i).copied(), plaintext.get(i).copied());
+            let agreement = match (o, p) {
+                (Some(o), Some(p)) if o == '_' || p == '_' => Agreement::Unknown,
+                (Some(o), Some(p)) if o == p => Agreement::Match,
+                (Some(_), Some(_)) => Agreement::Mismatch,
+                _ => Agreement::Missing,
+            };
+            let column = &mut columns[i % key_length];
+            match agreement {
+                Agreement::Match => column.compared += 1,
+                Agreement::Mismatch => {
+                    column.compared += 1;
+                    column.mismatches += 1;
+                }
+                Agreement::Unknown | Agreement::Missing => {}
+            }
+            CharDiff { output: o, plaintext: p, agreement }
+        })
+        .collect();
+
+    let count = |agreement: Agreement| chars.iter().filter(|c| c.agreement == agreement).count();
+    let (matches, mismatches) = (count(Agreement::Match), count(Agreement::Mismatch));
+    let compared = matches + mismatches;
+    let mut suspects: Vec<&KeyColumn> = columns
+        .iter()
+        .filter(|column| {
+            let rest = compared - column.compared;
+            let rest_rate = if rest == 0 { 0.0 } else { (mismatches - column.mismatches) as f64 / rest as f64 };
+            column.mismatches > 1 && column.rate() >= SUSPECT_RATE && column.rate() > SUSPECT_RATIO * rest_rate
+        })
+        .collect();
+    suspects.sort_by(|a, b| b.rate().total_cmp(&a.rate()).then(a.position.cmp(&b.position)));
+    let suspects = suspects.iter().map(|column| column.position).collect();
+
+    Comparison {
+        matches,
+        mismatches,
+        unknown: count(Agreement::Unknown),
+        missing: count(Agreement::Missing),
+        chars,
+        columns,
+        suspects,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        alphabet::Alphabet,
+        crypt::{K2p, K3p},
+        toolkit::{periodic_decrypt_in, periodic_encrypt_in, Family},
+    };
+
+    #[test]
+    fn a_wrong_key_letter_is_suspected() {
+        let alphabet = Alphabet::standard();
+        let ciphertext = periodic_encrypt_in(&alphabet, K3p, Family::Vigenere, "PALIMPSEST");
+        let output = periodic_decrypt_in(&alphabet, &ciphertext, Family::Vigenere, "PALIMQSEST");
+        let comparison = compare(&output, K3p, 10);
+        let wrong: Vec<usize> = comparison.chars.iter().enumerate().filter(|(_, c)| c.agreement == Agreement::Mismatch).map(|(i, _)| i).collect();
+        assert_eq!(wrong, (5..K3p.len()).step_by(10).collect::<Vec<_>>());
+        assert_eq!(comparison.suspects, [5]);
+    }
+
+    #[test]
+    fn scattered_misspellings_are_not_suspected() {
+        // K2 as carved has UNDERGRUUND; misspell two more words in other key positions.
+        let misspelled = K2p.replacen("LOCATION", "LOCATIEN", 1).replacen("GATHERED", "GETHERED", 1);
+        let comparison = compare(&misspelled, &K2p.replacen("UNDERGRUUND", "UNDERGROUND", 1), 8);
+        assert_eq!(comparison.mismatches, 3);
+        assert!(comparison.suspects.is_empty());
+    }
+}
@@ -0,0 +1,696 @@
+use std::{collections::HashMap, fmt};
+
+use serde::Serialize;
+
+use crate::{
+    alphabet::Alphabet,
+    error::{check_range, KryptonError, Result},
+    jobs::{Monitor, Unmonitored},
+    toolkit::{Family, Quagmire},
+};
+
+/// A cipher the known plaintext is tested against: a periodic family over one alphabet, or
+/// a Quagmire with its plaintext and ciphertext alphabets. An alphabet is keyed by its
+/// keyword, straight for an empty keyword, or unknown for `None` and placed by the known
+/// pairs alone.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum Hypothesis {
+    Periodic { family: Family, keyword: Option<String> },
+    Quagmire { variant: Quagmire, plain_key: Option<String>, cipher_key: Option<String> },
+}
+
+impl Hypothesis {
+    pub fn name(&self) -> String {
+        let keyed = |keyword: &Option<String>| match keyword.as_deref() {
+            None => "unknown".to_string(),
+            Some("") => "A-Z".to_string(),
+            Some(keyword) => keyword.to_string(),
+        };
+        match self {
+            Hypothesis::Periodic { family, keyword } => format!("{} ({})", family.name(), keyed(keyword)),
+            Hypothesis::Quagmire { variant: Quagmire::IV, plain_key, cipher_key } => {
+                format!("{} ({}, {})", Quagmire::IV.name(), keyed(plain_key), keyed(cipher_key))
+            }
+            Hypothesis::Quagmire { variant, plain_key, cipher_key } => {
+                let keyword = if *variant == Quagmire::II { cipher_key } else { plain_key };
+                format!("{} ({})", variant.name(), keyed(keyword))
+            }
+        }
+    }
+
+    /// Keywords of the plaintext and ciphertext alphabets, and whether they are one alphabet.
+    fn layout(&self) -> (Option<&str>, Option<&str>, bool) {
+        match self {
+            Hypothesis::Periodic { keyword, .. } => (keyword.as_deref(), keyword.as_deref(), true),
+            Hypothesis::Quagmire { variant, plain_key, cipher_key } => match variant {
+                Quagmire::I => (plain_key.as_deref(), Some(""), false),
+                Quagmire::II => (Some(""), cipher_key.as_deref(), false),
+                Quagmire::III => (plain_key.as_deref(), plain_key.as_deref(), true),
+                Quagmire::IV => (plain_key.as_deref(), cipher_key.as_deref(), false),
+            },
+        }
+    }
+
+    /// -1 where a column's key reverses the plaintext alphabet, as Beaufort does.
+    fn sign(&self) -> i64 {
+        match self {
+            Hypothesis::Periodic { family: Family::Beaufort, .. } => -1,
+            _ => 1,
+        }
+    }
+
+    /// The key index of a column that takes the place `p` of each plaintext symbol to
+    /// `sign * p + slide` in the ciphertext alphabet. For a Quagmire this is the slide
+    /// itself, whose indicator letter is the ciphertext symbol at that index.
+    fn key_index(&self, slide: usize, size: usize) -> usize {
+        match self {
+            Hypothesis::Periodic { family, .. } => family.key_index_in(0, slide, size),
+            Hypothesis::Quagmire { .. } => slide,
+        }
+    }
+}
+
+/// One known letter of the plaintext under the ciphertext letter at `position`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct KnownPair {
+    pub position: usize,
+    pub plain: char,
+    pub cipher: char,
+}
+
+/// Two known pairs that cannot both hold under a hypothesis, given the pairs before the
+/// first of them.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Contradiction {
+    pub first: KnownPair,
+    pub second: KnownPair,
+    pub reason: String,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}->{} at {} and {}->{} at {} {}",
+            self.first.plain, self.first.cipher, self.first.position,
+            self.second.plain, self.second.cipher, self.second.position, self.reason,
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum Outcome {
+    Contradicted(Contradiction),
+    Consistent {
+        /// Key letters fixed by the known pairs, `_` for columns with none. Over an unknown
+        /// alphabet they are read off the alphabets as shown, and rotating those changes them.
+        key: String,
+        /// The plaintext alphabet with every symbol the known pairs place and `_` for open
+        /// places, then each group of symbols placed only relative to each other.
+        plain_alphabet: String,
+        /// The ciphertext alphabet in the same form.
+        cipher_alphabet: String,
+        /// The ciphertext decrypted wherever the known pairs fix its plaintext, `_` elsewhere.
+        plaintext: String,
+        /// Known pairs already implied by the ones before them, and so actually tested.
+        checked: usize,
+    },
+}
+
+#[derive(Serialize)]
+pub struct ConstraintReport {
+    pub hypothesis: Hypothesis,
+    pub period: usize,
+    pub outcome: Outcome,
+}
+
+/// Every plaintext and ciphertext symbol pair where `crib`, aligned with the normalized
+/// ciphertext like `K4p`, has a symbol of `alphabet`.
+pub fn known_pairs(ciphertext: &str, crib: &str, alphabet: &Alphabet) -> Vec<KnownPair> {
+    alphabet
+        .normalize(ciphertext)
+        .chars()
+        .zip(crib.chars())
+        .enumerate()
+        .filter_map(|(position, (cipher, plain))| {
+            alphabet.index(plain).map(|p| KnownPair { position, plain: alphabet.symbol(p), cipher })
+        })
+        .collect()
+}
+
+/// How a union of two places turned out.
+enum Join {
+    Merged,
+    /// The places were already related this way, so the union tested it.
+    Tested,
+    Conflict,
+    /// The places were related with the opposite sign, which leaves their root one of two
+    /// places; that is left open rather than propagated.
+    Open,
+}
+
+/// Places of symbols in the alphabets of a tableau, as a union-find over nodes whose place
+/// is `sign * place of its parent + offset`, modulo the alphabet size. Places stay relative
+/// to each other until they are related to the last node, the anchor at place 0.
+#[derive(Clone)]
+struct Places {
+    size: i64,
+    parent: Vec<usize>,
+    sign: Vec<i64>,
+    offset: Vec<i64>,
+}
+
+/// A node's root with its place as `sign * place of the root + offset`.
+type Place = (usize, i64, i64);
+
+impl Places {
+    fn new(nodes: usize, size: usize) -> Places {
+        Places { size: size as i64, parent: (0..=nodes).collect(), sign: vec![1; nodes + 1], offset: vec![0; nodes + 1] }
+    }
+
+    fn anchor(&self) -> usize {
+        self.parent.len() - 1
+    }
+
+    /// The place of `root` when its group holds the anchor.
+    fn root_place(&mut self, root: usize) -> Option<i64> {
+        let (anchor_root, sign, offset) = self.find(self.anchor());
+        (anchor_root == root).then(|| (-sign * offset).rem_euclid(self.size))
+    }
+
+    /// `find`, except that a node in the anchor's group is given as `(anchor, 1, place)`.
+    fn locate(&mut self, node: usize) -> Place {
+        let (root, sign, offset) = self.find(node);
+        match self.root_place(root) {
+            Some(place) => (self.anchor(), 1, (sign * place + offset).rem_euclid(self.size)),
+            None => (root, sign, offset),
+        }
+    }
+
+    fn find(&mut self, node: usize) -> Place {
+        let parent = self.parent[node];
+        if parent == node {
+            return (node, 1, 0);
+        }
+        let (root, sign, offset) = self.find(parent);
+        self.parent[node] = root;
+        self.offset[node] = (self.sign[node] * offset + self.offset[node]).rem_euclid(self.size);
+        self.sign[node] *= sign;
+        (root, self.sign[node], self.offset[node])
+    }
+
+    /// Records that the place of `a` is `sign * place of b + offset`.
+    fn union(&mut self, a: usize, b: usize, sign: i64, offset: i64) -> Join {
+        let (root_a, sign_a, offset_a) = self.find(a);
+        let (root_b, sign_b, offset_b) = self.find(b);
+        let root_sign = sign_a * sign * sign_b;
+        let root_offset = (sign_a * (sign * offset_b + offset - offset_a)).rem_euclid(self.size);
+        if root_a != root_b {
+            self.parent[root_a] = root_b;
+            self.sign[root_a] = root_sign;
+            self.offset[root_a] = root_offset;
+            Join::Merged
+        } else if root_sign < 0 {
+            match self.root_place(root_a) {
+                Some(place) if (2 * place - root_offset).rem_euclid(self.size) == 0 => Join::Tested,
+                Some(_) => Join::Conflict,
+                None => Join::Open,
+            }
+        } else if root_offset == 0 {
+            Join::Tested
+        } else {
+            Join::Conflict
+        }
+    }
+}
+
+/// Why known pairs cannot all hold.
+#[derive(Clone, Copy)]
+enum Failure {
+    /// A pair needs two different places for one symbol.
+    Conflict,
+    /// A pair puts two symbols of one alphabet in the same place.
+    Collision { row: usize, first: usize, second: usize },
+    /// Every slide tried for the reference column fails.
+    Slides { column: usize },
+}
+
+impl Failure {
+    fn describe(&self, alphabet: &Alphabet, shared: bool) -> String {
+        match *self {
+            Failure::Conflict => "need two different places for one symbol".to_string(),
+            Failure::Collision { row, first, second } => {
+                let place = match row {
+                    0 if shared => "the alphabet",
+                    0 => "the plaintext alphabet",
+                    _ => "the ciphertext alphabet",
+                };
+                format!("put {} and {} in one place of {}", alphabet.symbol(first), alphabet.symbol(second), place)
+            }
+            Failure::Slides { column } => format!("fail for every slide of column {}", column),
+        }
+    }
+}
+
+/// A symbol not yet placed: its sign and offset from the root of its group, and itself.
+type Member = (i64, i64, char);
+
+/// One hypothesis at one period as places of the symbols of its alphabets: row 0 for the
+/// plaintext alphabet and row 1 for the ciphertext alphabet, unless both are one alphabet
+/// in row 0. Column `j` slides the plaintext alphabet by `slides[j]`, so a pair in it puts
+/// its ciphertext symbol at `sign * place of its plaintext symbol + slide`. A known slide
+/// places the symbols of every pair in its column relative to each other, and a pair whose
+/// symbols are already placed relative to each other fixes the slide of its column.
+#[derive(Clone)]
+struct Tableau {
+    size: usize,
+    period: usize,
+    shared: bool,
+    sign: i64,
+    places: Places,
+    slides: Vec<Option<i64>>,
+    /// Whether each row was placed by a keyword.
+    keyed: [bool; 2],
+    checked: usize,
+}
+
+impl Tableau {
+    fn new(hypothesis: &Hypothesis, alphabet: &Alphabet, period: usize) -> Tableau {
+        let size = alphabet.len();
+        let (plain_key, cipher_key, shared) = hypothesis.layout();
+        let mut tableau = Tableau {
+            size,
+            period,
+            shared,
+            sign: hypothesis.sign(),
+            places: Places::new(2 * size, size),
+            slides: vec![None; period],
+            keyed: [plain_key.is_some(), cipher_key.is_some()],
+            checked: 0,
+        };
+        let rows = if shared { vec![(0, plain_key)] } else { vec![(0, plain_key), (1, cipher_key)] };
+        for (row, keyword) in rows {
+            let Some(keyword) = keyword else { continue };
+            let keyed = alphabet.keyed(keyword);
+            for symbol in 0..size {
+                let place = keyed.index(alphabet.symbol(symbol)).unwrap_or(symbol);
+                let (node, anchor) = (tableau.node(row, symbol), tableau.anchor());
+                tableau.places.union(node, anchor, 1, place as i64);
+            }
+        }
+        tableau
+    }
+
+    fn node(&self, row: usize, symbol: usize) -> usize {
+        row * self.size + symbol
+    }
+
+    /// The node at place 0, which keyed alphabets are placed against.
+    fn anchor(&self) -> usize {
+        self.places.anchor()
+    }
+
+    fn cipher_row(&self) -> usize {
+        if self.shared { 0 } else { 1 }
+    }
+
+    /// Slides worth trying for the first column to be fixed, one for each that no
+    /// rearranging of the unknown alphabets can turn into another. Rotating one of two
+    /// separate alphabets moves every slide alike, so 0 serves. Rotating a shared one keeps
+    /// the slides, but multiplying its places by a number prime to the size multiplies them
+    /// too, which leaves 0 and the divisors of the size; under Beaufort a rotation adds
+    /// twice its amount, which leaves 0 and, for an even size, 1.
+    fn reference_slides(&self) -> Vec<i64> {
+        let size = self.size as i64;
+        if self.keyed[0] && (self.shared || self.keyed[1]) {
+            (0..size).collect()
+        } else if !self.shared {
+            vec![0]
+        } else if self.sign > 0 {
+            std::iter::once(0).chain((1..size).filter(|d| size % d == 0)).collect()
+        } else {
+            (0..2 - size % 2).collect()
+        }
+    }
+
+    /// Applies the pairs of every column with a known slide and fixes the slides that
+    /// those pairs determine, until nothing changes, then checks no two symbols of one
+    /// alphabet share a place. `pairs` are position, plaintext index and ciphertext index.
+    fn propagate(&mut self, pairs: &[(usize, usize, usize)]) -> std::result::Result<(), Failure> {
+        let mut applied = vec![false; pairs.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, &(position, plain, cipher)) in pairs.iter().enumerate() {
+                let column = position % self.period;
+                if applied[i] {
+                    continue;
+                }
+                let plain_node = self.node(0, plain);
+                let cipher_node = self.node(self.cipher_row(), cipher);
+                let Some(slide) = self.slides[column] else {
+                    let (plain_root, plain_sign, plain_offset) = self.places.locate(plain_node);
+                    let (cipher_root, cipher_sign, cipher_offset) = self.places.locate(cipher_node);
+                    let anchored = plain_root == self.anchor();
+                    if plain_root == cipher_root && (anchored || cipher_sign == self.sign * plain_sign) {
+                        self.slides[column] = Some((cipher_offset - self.sign * plain_offset).rem_euclid(self.size as i64));
+                        applied[i] = true;
+                        changed = true;
+                    }
+                    continue;
+                };
+                applied[i] = true;
+                changed = true;
+                match self.places.union(cipher_node, plain_node, self.sign, slide) {
+                    Join::Tested => self.checked += 1,
+                    Join::Conflict => return Err(Failure::Conflict),
+                    Join::Merged | Join::Open => {}
+                }
+            }
+        }
+        self.collision()
+    }
+
+    /// Fails when two symbols of one alphabet are in the same place.
+    fn collision(&mut self) -> std::result::Result<(), Failure> {
+        let rows = if self.shared { 1 } else { 2 };
+        let mut places = HashMap::new();
+        for row in 0..rows {
+            for symbol in 0..self.size {
+                let place = self.places.locate(self.node(row, symbol));
+                if let Some(first) = places.insert((row, place), symbol) {
+                    return Err(Failure::Collision { row, first, second: symbol });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Root places for one placing of the alphabets: the anchor's group where it is, and for
+    /// an alphabet with no symbol in it, its largest group of two or more symbols at the
+    /// first root place that keeps them apart. Symbols of the remaining groups are left
+    /// unplaced.
+    fn roots(&mut self) -> HashMap<usize, i64> {
+        let mut roots = HashMap::from([(self.anchor(), 0)]);
+        for row in [0, self.cipher_row()] {
+            let mut counts: Vec<(usize, usize)> = Vec::new();
+            for symbol in 0..self.size {
+                let (root, _, _) = self.places.locate(self.node(row, symbol));
+                match counts.iter_mut().find(|(r, _)| *r == root) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((root, 1)),
+                }
+            }
+            if counts.iter().any(|(root, _)| roots.contains_key(root)) {
+                continue;
+            }
+            let largest = counts.iter().rev().filter(|&&(_, count)| count > 1).max_by_key(|&&(_, count)| count);
+            if let Some(&(root, _)) = largest {
+                if let Some(place) = self.free_place(root) {
+                    roots.insert(root, place);
+                }
+            }
+        }
+        roots
+    }
+
+    /// The first place for `root` at which no two symbols of one alphabet in its group share
+    /// a place. Any place will do unless the group mixes signs, as Beaufort's do.
+    fn free_place(&mut self, root: usize) -> Option<i64> {
+        let rows = if self.shared { 1 } else { 2 };
+        let mut members = Vec::new();
+        for row in 0..rows {
+            for symbol in 0..self.size {
+                let (group, sign, offset) = self.places.locate(self.node(row, symbol));
+                if group == root {
+                    members.push((row, sign, offset));
+                }
+            }
+        }
+        let size = self.size as i64;
+        (0..size).find(|&place| {
+            let mut places: Vec<(usize, i64)> = members.iter().map(|&(row, sign, offset)| (row, (sign * place + offset).rem_euclid(size))).collect();
+            places.sort();
+            places.windows(2).all(|pair| pair[0] != pair[1])
+        })
+    }
+
+    fn place(&mut self, row: usize, symbol: usize, roots: &HashMap<usize, i64>) -> Option<usize> {
+        let (root, sign, offset) = self.places.locate(self.node(row, symbol));
+        roots.get(&root).map(|value| (sign * value + offset).rem_euclid(self.size as i64) as usize)
+    }
+
+    /// The alphabet of `row` with its placed symbols and `_` elsewhere, then each other
+    /// group of two or more symbols from its first symbol to its last.
+    fn render(&mut self, row: usize, alphabet: &Alphabet, roots: &HashMap<usize, i64>) -> String {
+        let size = self.size;
+        let mut line = vec!['_'; size];
+        let mut groups: Vec<(usize, Vec<Member>)> = Vec::new();
+        for symbol in 0..size {
+            match self.place(row, symbol, roots) {
+                Some(place) => line[place] = alphabet.symbol(symbol),
+                None => {
+                    let (root, sign, offset) = self.places.locate(self.node(row, symbol));
+                    let entry = (sign, offset, alphabet.symbol(symbol));
+                    match groups.iter_mut().find(|(r, _)| *r == root) {
+                        Some((_, group)) => group.push(entry),
+                        None => groups.push((root, vec![entry])),
+                    }
+                }
+            }
+        }
+        let mut text: String = line.into_iter().collect();
+        for (root, members) in groups.into_iter().filter(|(_, group)| group.len() > 1) {
+            let Some(place) = self.free_place(root) else { continue };
+            let mut group: Vec<(usize, char)> =
+                members.into_iter().map(|(sign, offset, symbol)| ((sign * place + offset).rem_euclid(size as i64) as usize, symbol)).collect();
+            group.sort();
+            // Start after the widest gap, so the group reads in as short a stretch as it can.
+            let gap = |i: usize| (group[(i + 1) % group.len()].0 + size - group[i].0) % size;
+            let widest = (0..group.len()).rev().max_by_key(|&i| gap(i)).unwrap_or(0);
+            text.push(' ');
+            for i in (1..=group.len()).map(|step| (widest + step) % group.len()) {
+                text.push(group[i].1);
+                if i != widest {
+                    text.extend(std::iter::repeat_n('_', gap(i) - 1));
+                }
+            }
+        }
+        text
+    }
+
+    /// The key letter of `column` in the placing of `roots`, when its slide and the symbol
+    /// at the place the slide names are both known.
+    fn key(&mut self, column: usize, hypothesis: &Hypothesis, alphabet: &Alphabet, roots: &HashMap<usize, i64>) -> Option<char> {
+        let index = hypothesis.key_index(self.slides[column]? as usize, self.size);
+        let row = match hypothesis {
+            Hypothesis::Periodic { .. } => 0,
+            Hypothesis::Quagmire { .. } => self.cipher_row(),
+        };
+        (0..self.size).find(|&symbol| self.place(row, symbol, roots) == Some(index)).map(|symbol| alphabet.symbol(symbol))
+    }
+
+    /// The plaintext symbol under ciphertext symbol `cipher` at `position`, when fixed.
+    fn decrypt(&mut self, position: usize, cipher: usize) -> Option<usize> {
+        let slide = self.slides[position % self.period]?;
+        let (root, sign, offset) = self.places.locate(self.node(self.cipher_row(), cipher));
+        let sign = if root == self.anchor() { 1 } else { self.sign * sign };
+        let target = (root, sign, (self.sign * (offset - slide)).rem_euclid(self.size as i64));
+        (0..self.size).find(|&symbol| self.places.locate(self.node(0, symbol)) == target)
+    }
+
+    fn outcome(mut self, ciphertext: &str, alphabet: &Alphabet, hypothesis: &Hypothesis) -> Outcome {
+        let roots = self.roots();
+        let plain_alphabet = self.render(0, alphabet, &roots);
+        let cipher_alphabet = self.render(self.cipher_row(), alphabet, &roots);
+        let key = (0..self.period).map(|column| self.key(column, hypothesis, alphabet, &roots).unwrap_or('_')).collect();
+        let plaintext = alphabet
+            .indices(ciphertext)
+            .into_iter()
+            .enumerate()
+            .map(|(position, cipher)| self.decrypt(position, cipher).map_or('_', |plain| alphabet.symbol(plain)))
+            .collect();
+        Outcome::Consistent { key, plain_alphabet, cipher_alphabet, plaintext, checked: self.checked }
+    }
+}
+
+/// Every placing of the alphabets that `pairs` leave consistent. When no slide follows from
+/// keyed alphabets, the column with the most pairs is tried at each of its
+/// `reference_slides`, and each that holds is one placing; columns no pair fixes stay open.
+fn assignments(hypothesis: &Hypothesis, alphabet: &Alphabet, period: usize, pairs: &[(usize, usize, usize)]) -> std::result::Result<Vec<Tableau>, Failure> {
+    let mut tableau = Tableau::new(hypothesis, alphabet, period);
+    tableau.propagate(pairs)?;
+    if tableau.slides.iter().any(Option::is_some) {
+        return Ok(vec![tableau]);
+    }
+    let mut counts = vec![0; period];
+    for &(position, _, _) in pairs {
+        counts[position % period] += 1;
+    }
+    let reference = (0..period).rev().max_by_key(|&column| counts[column]).unwrap_or(0);
+    let mut found = Vec::new();
+    let mut failure = Failure::Conflict;
+    for slide in tableau.reference_slides() {
+        let mut branch = tableau.clone();
+        branch.slides[reference] = Some(slide);
+        match branch.propagate(pairs) {
+            Ok(()) => found.push(branch),
+            Err(reason) => failure = reason,
+        }
+    }
+    if !found.is_empty() {
+        Ok(found)
+    } else if tableau.reference_slides().len() == 1 {
+        Err(failure)
+    } else {
+        Err(Failure::Slides { column: reference })
+    }
+}
+
+/// Tests one hypothesis at one period against every known pair, giving each placing of its
+/// alphabets that the pairs leave consistent. Keyed alphabets are placed up front; unknown
+/// ones are built up from the pairs alone. Otherwise the first pair that no placing holds
+/// with contradicts the hypothesis, together with the earliest pair before it that it
+/// cannot hold with.
+pub fn check_hypothesis(ciphertext: &str, known: &[KnownPair], alphabet: &Alphabet, hypothesis: &Hypothesis, period: usize) -> Vec<Outcome> {
+    let (known, pairs): (Vec<KnownPair>, Vec<(usize, usize, usize)>) = known
+        .iter()
+        .filter_map(|&pair| Some((pair, (pair.position, alphabet.index(pair.plain)?, alphabet.index(pair.cipher)?))))
+        .unzip();
+    let tableaux = match assignments(hypothesis, alphabet, period, &pairs) {
+        Ok(tableaux) => tableaux,
+        Err(_) => {
+            let fails = |pairs: &[(usize, usize, usize)]| assignments(hypothesis, alphabet, period, pairs).err();
+            let second = (0..pairs.len()).find(|&i| fails(&pairs[..=i]).is_some()).unwrap_or(pairs.len().saturating_sub(1));
+            let (first, failure) = (0..second)
+                .find_map(|first| {
+                    let mut prefix = pairs[..=first].to_vec();
+                    prefix.push(pairs[second]);
+                    fails(&prefix).map(|failure| (first, failure))
+                })
+                .unwrap_or_else(|| (second, fails(&pairs[..=second]).unwrap_or(Failure::Conflict)));
+            let (_, _, shared) = hypothesis.layout();
+            let reason = failure.describe(alphabet, shared);
+            return vec![Outcome::Contradicted(Contradiction { first: known[first], second: known[second], reason })];
+        }
+    };
+    tableaux.into_iter().map(|tableau| tableau.outcome(ciphertext, alphabet, hypothesis)).collect()
+}
+
+/// The periodic families over the straight alphabet; then, for an unknown alphabet and
+/// each keyword's keyed alphabet, the periodic families and Quagmire I to III over it; and
+/// Quagmire IV for every ordered pair of those alphabets, two unknown ones included.
+pub fn hypotheses(keywords: &[&str]) -> Vec<Hypothesis> {
+    let keyed: Vec<Option<String>> = std::iter::once(None).chain(distinct_keywords(keywords).filter(|k| !k.is_empty()).map(Some)).collect();
+    let mut list: Vec<Hypothesis> = Family::ALL.into_iter().map(|family| Hypothesis::Periodic { family, keyword: Some(String::new()) }).collect();
+    for keyword in &keyed {
+        for family in Family::ALL {
+            list.push(Hypothesis::Periodic { family, keyword: keyword.clone() });
+        }
+        for variant in [Quagmire::I, Quagmire::II, Quagmire::III] {
+            list.push(Hypothesis::Quagmire { variant, plain_key: keyword.clone(), cipher_key: keyword.clone() });
+        }
+    }
+    for plain_key in &keyed {
+        for cipher_key in keyed.iter().filter(|&k| k.is_none() || k != plain_key) {
+            list.push(Hypothesis::Quagmire { variant: Quagmire::IV, plain_key: plain_key.clone(), cipher_key: cipher_key.clone() });
+        }
+    }
+    list
+}
+
+/// The straight alphabet's empty keyword followed by each distinct keyword in upper case.
+fn distinct_keywords<'a>(keywords: &'a [&str]) -> impl Iterator<Item = String> + 'a {
+    let mut seen = vec![String::new()];
+    std::iter::once(String::new()).chain(keywords.iter().filter_map(move |k| {
+        let k: String = k.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+        (!seen.contains(&k)).then(|| {
+            seen.push(k.clone());
+            k
+        })
+    }))
+}
+
+/// Tests every hypothesis from `hypotheses(keywords)` at every period from 1 to
+/// `max_period` against the known plaintext in `crib`. No keywords are needed: unknown
+/// alphabets are always tried. Consistent hypotheses come first, the most thoroughly
+/// tested leading, then the contradicted ones in order.
+pub fn solve_constraints(ciphertext: &str, crib: &str, alphabet: &Alphabet, keywords: &[&str], max_period: usize) -> Result<Vec<ConstraintReport>> {
+    solve_constraints_monitored(ciphertext, crib, alphabet, keywords, max_period, &Unmonitored)
+}
+
+/// `solve_constraints` reporting each hypothesis as it is done, and returning the reports
+/// of the hypotheses done so far when cancelled.
+pub fn solve_constraints_monitored(
+    ciphertext: &str,
+    crib: &str,
+    alphabet: &Alphabet,
+    keywords: &[&str],
+    max_period: usize,
+    monitor: &dyn Monitor,
+) -> Result<Vec<ConstraintReport>> {
+    let known = known_pairs(ciphertext, crib, alphabet);
+    if known.is_empty() {
+        return Err(KryptonError::EmptyText("Plaintext"));
+    }
+    check_range("Max Key Length", max_period, 1, alphabet.normalize(ciphertext).chars().count())?;
+
+    let hypotheses = hypotheses(keywords);
+    let mut reports = Vec::new();
+    for (done, hypothesis) in hypotheses.iter().enumerate() {
+        if monitor.cancelled() {
+            break;
+        }
+        monitor.progress(done, hypotheses.len());
+        for period in 1..=max_period {
+            for outcome in check_hypothesis(ciphertext, &known, alphabet, hypothesis, period) {
+                reports.push(ConstraintReport { hypothesis: hypothesis.clone(), period, outcome });
+            }
+        }
+    }
+    let checked = |report: &ConstraintReport| match report.outcome {
+        Outcome::Consistent { checked, .. } => Some(checked),
+        Outcome::Contradicted(_) => None,
+    };
+    reports.sort_by(|a, b| checked(b).cmp(&checked(a)).then(a.period.cmp(&b.period)));
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::{K1, K1p};
+
+    fn outcomes<'a>(reports: &'a [ConstraintReport], name: &str, period: usize) -> Vec<&'a Outcome> {
+        reports.iter().filter(|r| r.period == period && r.hypothesis.name() == name).map(|r| &r.outcome).collect()
+    }
+
+    #[test]
+    fn keyword_recovers_k1() {
+        let reports = solve_constraints(K1, K1p, &Alphabet::standard(), &["KRYPTOS"], 10).unwrap();
+        let outcome = outcomes(&reports, "Quagmire III (KRYPTOS)", 10);
+        match outcome[..] {
+            [Outcome::Consistent { key, plaintext, .. }] => {
+                assert_eq!(key, "PALIMPSEST");
+                assert_eq!(plaintext, K1p);
+            }
+            _ => panic!("{outcome:?}"),
+        }
+        assert!(outcomes(&reports, "Vigenere (A-Z)", 10).iter().all(|o| matches!(o, Outcome::Contradicted(_))));
+    }
+
+    #[test]
+    fn unknown_alphabets_need_no_keyword() {
+        let crib: String = K1p.chars().enumerate().map(|(i, c)| if i < 40 { c } else { '_' }).collect();
+        let reports = solve_constraints(K1, &crib, &Alphabet::standard(), &[], 10).unwrap();
+        let consistent = outcomes(&reports, "Quagmire III (unknown)", 10);
+        assert!(!consistent.is_empty());
+        for outcome in consistent {
+            let Outcome::Consistent { plain_alphabet, plaintext, .. } = outcome else { panic!("{outcome:?}") };
+            assert!(plain_alphabet.contains('_'), "{plain_alphabet}");
+            for (decrypted, expected) in plaintext.chars().zip(K1p.chars()) {
+                assert!(decrypted == '_' || decrypted == expected, "{plaintext}");
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    alphabet::Alphabet,
+    jobs::{Monitor, Unmonitored},
+    ngram::english_fitness,
+    toolkit::Family,
+};
+
+/// The key fragment one crib implies at one offset under one family and tableau.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CribMatch {
+    /// Symbols of the normalized ciphertext before the crib.
+    pub offset: usize,
+    pub family: Family,
+    /// Keyword of the tableau alphabet, empty for the straight alphabet.
+    pub keyword: String,
+    /// Implied key symbols, `_` under crib characters outside the alphabet.
+    pub fragment: String,
+    /// Shift at which the fragment agrees with itself most, from 1 to half its length.
+    pub period: usize,
+    /// Share of known symbol pairs `period` apart that agree, from 0 to 1.
+    pub periodicity: f64,
+    /// Longest run of two or more symbols that occurs twice in the fragment.
+    pub repeat: String,
+    /// Quadgram fitness of the fragment.
+    pub fitness: f64,
+    /// Sum of the periodicity, repeat and word scores, each from 0 to 1.
+    pub score: f64,
+}
+
+/// Columns of the crib dragging table, each a way of sorting the matches.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CribColumn {
+    Offset,
+    Family,
+    Tableau,
+    Fragment,
+    Period,
+    Periodicity,
+    Repeat,
+    Fitness,
+    #[default]
+    Score,
+}
+
+impl CribColumn {
+    pub const ALL: [CribColumn; 9] = [
+        CribColumn::Offset,
+        CribColumn::Family,
+        CribColumn::Tableau,
+        CribColumn::Fragment,
+        CribColumn::Period,
+        CribColumn::Periodicity,
+        CribColumn::Repeat,
+        CribColumn::Fitness,
+        CribColumn::Score,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CribColumn::Offset => "OFFSET",
+            CribColumn::Family => "FAMILY",
+            CribColumn::Tableau => "TABLEAU",
+            CribColumn::Fragment => "FRAGMENT",
+            CribColumn::Period => "PERIOD",
+            CribColumn::Periodicity => "PERIODICITY",
+            CribColumn::Repeat => "REPEAT",
+            CribColumn::Fitness => "FITNESS",
+            CribColumn::Score => "SCORE",
+        }
+    }
+
+    /// Orders two matches by this column, ascending.
+    pub fn compare(&self, a: &CribMatch, b: &CribMatch) -> Ordering {
+        match self {
+            CribColumn::Offset => a.offset.cmp(&b.offset),
+            CribColumn::Family => a.family.name().cmp(b.family.name()),
+            CribColumn::Tableau => a.keyword.cmp(&b.keyword),
+            CribColumn::Fragment => a.fragment.cmp(&b.fragment),
+            CribColumn::Period => a.period.cmp(&b.period),
+            CribColumn::Periodicity => a.periodicity.total_cmp(&b.periodicity),
+            CribColumn::Repeat => a.repeat.len().cmp(&b.repeat.len()).then_with(|| a.repeat.cmp(&b.repeat)),
+            CribColumn::Fitness => a.fitness.total_cmp(&b.fitness),
+            CribColumn::Score => a.score.total_cmp(&b.score),
+        }
+    }
+}
+
+/// Sorts by `column`, breaking ties by ascending offset in either direction so the order
+/// is stable between clicks.
+pub fn sort_matches(matches: &mut [CribMatch], column: CribColumn, descending: bool) {
+    matches.sort_by(|a, b| {
+        let order = column.compare(a, b);
+        if descending { order.reverse() } else { order }.then_with(|| a.offset.cmp(&b.offset))
+    });
+}
+
+/// The key that takes each plaintext character to the ciphertext character in the same
+/// position under `family`, as symbols of `alphabet`. Positions where either character is
+/// outside the alphabet, such as the `_` of `K4p`, give `_`. Stops at the shorter text.
+pub fn implied_key(alphabet: &Alphabet, family: Family, ciphertext: &str, plaintext: &str) -> String {
+    implied_key_indices(alphabet, family, ciphertext, plaintext)
+        .into_iter()
+        .map(|k| k.map_or('_', |k| alphabet.symbol(k)))
+        .collect()
+}
+
+/// `implied_key` as indices into `alphabet`, `None` where the key is unknown, so an
+/// alphabet that has `_` as a symbol is not mistaken for a gap.
+fn implied_key_indices(alphabet: &Alphabet, family: Family, ciphertext: &str, plaintext: &str) -> Vec<Option<usize>> {
+    ciphertext
+        .chars()
+        .zip(plaintext.chars())
+        .map(|(c, p)| Some(family.key_index_in(alphabet.index(p)?, alphabet.index(c)?, alphabet.len())))
+        .collect()
+}
+
+/// Quadgram fitness of random letters and of English words, between which the word score
+/// runs from 0 to 1.
+const FITNESS_RANGE: (f64, f64) = (-8.0, -5.0);
+
+/// The shift from 1 to half the fragment's length at which most known symbols agree with
+/// the symbol that far ahead, and the share that agree. The shortest shift wins ties.
+fn best_period(fragment: &[Option<usize>]) -> (usize, f64) {
+    (1..=fragment.len() / 2)
+        .filter_map(|period| {
+            let pairs: Vec<bool> = fragment
+                .iter()
+                .zip(&fragment[period..])
+                .filter_map(|(a, b)| Some(a.as_ref()? == b.as_ref()?))
+                .collect();
+            (!pairs.is_empty()).then(|| (period, pairs.iter().filter(|&&same| same).count() as f64 / pairs.len() as f64))
+        })
+        .fold((0, 0.0), |best, candidate| if candidate.1 > best.1 { candidate } else { best })
+}
+
+/// Longest run of known symbols that appears at two different positions, at least two long.
+fn longest_repeat(fragment: &[Option<usize>]) -> &[Option<usize>] {
+    let mut longest: &[Option<usize>] = &[];
+    for i in 0..fragment.len() {
+        for j in i + 1..fragment.len() {
+            let length = fragment[i..]
+                .iter()
+                .zip(&fragment[j..])
+                .take_while(|(a, b)| a.is_some() && a == b)
+                .count();
+            if length >= 2 && length > longest.len() {
+                longest = &fragment[i..i + length];
+            }
+        }
+    }
+    longest
+}
+
+/// Slides `crib` along `ciphertext` and works out the key fragment it implies at every
+/// offset for every family, over `alphabet` and, when `keyword` is not empty, over the
+/// alphabet keyed by it as in a keyed Vigenere tableau. Each fragment is scored for being
+/// periodic, for repeating a run of symbols and for reading like English, best first.
+/// Ciphertext outside the alphabet is dropped; whitespace in the crib is ignored and any
+/// other character outside the alphabet is a wildcard.
+pub fn crib_drag(ciphertext: &str, crib: &str, alphabet: &Alphabet, keyword: &str) -> Vec<CribMatch> {
+    crib_drag_monitored(ciphertext, crib, alphabet, keyword, &Unmonitored)
+}
+
+/// `crib_drag` reporting each offset as it is done, and returning the matches so far,
+/// best first, when cancelled.
+pub fn crib_drag_monitored(ciphertext: &str, crib: &str, alphabet: &Alphabet, keyword: &str, monitor: &dyn Monitor) -> Vec<CribMatch> {
+    let crib: String = crib.chars().filter(|c| !c.is_whitespace()).collect();
+    let length = crib.chars().count();
+    let mut tableaux = vec![(String::new(), alphabet.clone())];
+    if !alphabet.indices(keyword).is_empty() {
+        tableaux.push((keyword.to_uppercase(), alphabet.keyed(keyword)));
+    }
+
+    let offsets = |tableau: &Alphabet| (tableau.normalize(ciphertext).chars().count() + 1).saturating_sub(length);
+    let total: usize = tableaux.iter().map(|(_, tableau)| offsets(tableau)).sum();
+    let mut done = 0;
+    let mut matches = Vec::new();
+    for (keyword, tableau) in &tableaux {
+        let cipher: Vec<char> = tableau.normalize(ciphertext).chars().collect();
+        if length == 0 || cipher.len() < length {
+            continue;
+        }
+        for offset in 0..=cipher.len() - length {
+            if monitor.cancelled() {
+                break;
+            }
+            monitor.progress(done, total);
+            done += 1;
+            let window: String = cipher[offset..offset + length].iter().collect();
+            for family in Family::ALL {
+                let symbols = implied_key_indices(tableau, family, &window, &crib);
+                let fragment: String = symbols.iter().map(|k| k.map_or('_', |k| tableau.symbol(k))).collect();
+                let (period, periodicity) = best_period(&symbols);
+                let repeat: String = longest_repeat(&symbols).iter().flatten().map(|&i| tableau.symbol(i)).collect();
+                let known: String = symbols.iter().flatten().map(|&i| tableau.symbol(i)).collect();
+                let fitness = english_fitness(&known);
+                let word = ((fitness - FITNESS_RANGE.0) / (FITNESS_RANGE.1 - FITNESS_RANGE.0)).clamp(0.0, 1.0);
+                let repeated = repeat.chars().count() as f64 / (length / 2).max(1) as f64;
+                matches.push(CribMatch {
+                    offset,
+                    family,
+                    keyword: keyword.clone(),
+                    fragment,
+                    period,
+                    periodicity,
+                    repeat,
+                    fitness,
+                    score: periodicity + repeated.min(1.0) + word,
+                });
+            }
+        }
+    }
+    sort_matches(&mut matches, CribColumn::Score, true);
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crypt::K2p, toolkit::periodic_encrypt_in};
+
+    fn with_offset(offset: usize, score: f64) -> CribMatch {
+        CribMatch {
+            offset,
+            family: Family::Vigenere,
+            keyword: String::new(),
+            fragment: String::new(),
+            period: 1,
+            periodicity: 0.0,
+            repeat: String::new(),
+            fitness: 0.0,
+            score,
+        }
+    }
+
+    #[test]
+    fn dragging_finds_a_planted_periodic_key() {
+        let alphabet = Alphabet::standard();
+        let ciphertext = periodic_encrypt_in(&alphabet, K2p, Family::Beaufort, "PALIMPSEST");
+        let matches = crib_drag(&ciphertext, &K2p[120..150], &alphabet, "");
+        assert_eq!((matches[0].offset, matches[0].family), (120, Family::Beaufort));
+        assert_eq!((matches[0].period, matches[0].periodicity), (10, 1.0));
+        assert!(matches[0].fragment.starts_with("PALIMPSEST"));
+    }
+
+    #[test]
+    fn ties_are_broken_by_ascending_offset_both_ways() {
+        let mut matches = vec![with_offset(3, 1.0), with_offset(1, 1.0), with_offset(2, 2.0), with_offset(0, 1.0)];
+        let offsets = |matches: &[CribMatch]| matches.iter().map(|m| m.offset).collect::<Vec<_>>();
+        sort_matches(&mut matches, CribColumn::Score, false);
+        assert_eq!(offsets(&matches), [0, 1, 3, 2]);
+        sort_matches(&mut matches, CribColumn::Score, true);
+        assert_eq!(offsets(&matches), [2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn an_underscore_in_the_alphabet_is_a_key_symbol() {
+        let alphabet = Alphabet::custom("ABCDEFGHIJKLMNOPQRSTUVWXYZ_").unwrap();
+        let ciphertext = periodic_encrypt_in(&alphabet, &K2p[..60], Family::Vigenere, "K_Y");
+        let matches = crib_drag(&ciphertext, &K2p[..12], &alphabet, "");
+        assert_eq!(matches[0].fragment, "K_YK_YK_YK_Y");
+        assert_eq!(matches[0].repeat, "K_YK_YK_Y");
+        assert_eq!(implied_key(&Alphabet::standard(), Family::Vigenere, "AB", "A_"), "A_");
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(non_upper_case_globals)]
+
 pub const K1: &str =  "EMUFPHZLRFAXYUSDJKZLDKRNSHGNFIVJYQTQUXQBQVYUVLLTREVJYQTMKYRDMFD";
 pub const K1p: &str = "BETWEENSUBTLESHADINGANDTHEABSENCEOFLIGHTLIESTHENUANCEOFIQLUSION";
 
@@ -1,25 +1,20 @@
-use crate::{analysis::{aster_score, best_phi, match_percentage, substitution_cipher_score}, ALPHABET};
+use serde::Serialize;
+
+use crate::{
+    alphabet::Alphabet,
+    analysis::substitution_cipher_score,
+    crypt::ALPHABET,
+    error::{check_key, check_range, require_key, KryptonError, Result},
+    toolkit::check_tableau,
+};
+
+/// Keyword letters (first occurrence only) followed by the rest of the alphabet in order.
+pub fn keyed_alphabet(keyword: &str) -> Vec<char> {
+    Alphabet::standard().keyed(keyword).symbols().to_vec()
+}
 
 pub fn generate_vigenere_table(keyword1: &str, keyword2: &str) -> Vec<Vec<char>> {
-    let alphabet: Vec<char> = "ABCDEFGHIJKLMNOPQRSTUVWXYZ".chars().collect();
-    let key1: Vec<char> = keyword1.to_uppercase().chars().collect();
-    let key2: Vec<char> = keyword2.to_uppercase().chars().collect();
-
-    let filtered_alphabet: Vec<char> = alphabet.into_iter().filter(|&c| !key1.contains(&c)).collect();
-    let combined_alphabet: Vec<char> = [&key1[..], &filtered_alphabet[..]].concat();
-
-    let size = key2.len();
-    let mut table: Vec<Vec<char>> = vec![vec![' '; 26]; size + 1];
-
-    for (i, &c) in key2.iter().enumerate() {
-        let mut index = combined_alphabet.iter().position(|&x| x == c).unwrap();
-        for j in 0..26 {
-            table[i + 1][j] = combined_alphabet[index % 26];
-            index += 1;
-        }
-    }
-    table[0] = combined_alphabet;
-    table
+    Alphabet::standard().keyed(keyword1).tableau(keyword2)
 }
 
 pub fn vig2table(keyword1: &str, keyword2: &str) -> Vec<Vec<char>> {
@@ -30,21 +25,27 @@ pub fn vig1table(keyword2: &str) -> Vec<Vec<char>> {
     generate_vigenere_table("ABCDEFGHIJKLMNOPQRSTUVWXYZ", keyword2)
 }
 
-pub fn print_vigenere_table(table: &Vec<Vec<char>>) {
+pub fn print_vigenere_table(table: &[Vec<char>]) {
     println!();
-    for i in 0..table.len() {
-        let mut string = "".to_string();
-        for j in 0..table[0].len() {
-            string.push(char::from(table[i][j]));
+    print!("{}", format_vigenere_table(table));
+}
+
+/// The rows of `table`, each cell followed by a space and each row by a line break.
+pub fn format_vigenere_table(table: &[Vec<char>]) -> String {
+    let mut string = String::new();
+    for row in table {
+        for &cell in row {
+            string.push(cell);
             string.push(' ');
         }
-        println!("{}", string);
+        string.push('\n');
     }
+    string
 }
 
 pub fn every_nth_letter(s: &str, n: usize) -> String {
     s.char_indices()
-        .filter(|(i, _)| i % n == 0)
+        .filter(|(i, _)| n > 0 && i % n == 0)
         .map(|(_, c)| c)
         .collect()
 }
@@ -136,25 +137,43 @@ pub fn kryptos2(
 */
 
 
+/// The cipher alphabets `keyless` rebuilds from known plaintext.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct KeylessReport {
+    /// Ciphertext letters under each key position, in order.
+    pub columns: Vec<String>,
+    /// `substitution_cipher_score` of each column against its plaintext column.
+    pub scores: Vec<Option<f64>>,
+    /// The plain alphabet followed by one cipher alphabet per key position, blank where
+    /// the plaintext never shows the letter.
+    pub table: Vec<Vec<char>>,
+    /// The ciphertext decoded through `table`.
+    pub decoded: String,
+}
+
+impl KeylessReport {
+    /// The cipher alphabets as the key of a `Periodic Substitution`, `_` where unknown.
+    pub fn alphabets(&self) -> String {
+        self.table[1..].iter().map(|row| row.iter().map(|&c| if c == ' ' { '_' } else { c }).collect::<String>()).collect::<Vec<String>>().join(",")
+    }
+}
+
+/// Writes ciphertext and known plaintext in rows of `max_key_length` and, for each column,
+/// records which ciphertext letter every plaintext letter became.
 pub fn keyless(
     encrypted_text: &str,
     plaintext: &str,
     max_key_length: usize,
-) {
+) -> KeylessReport {
     let enc_block = string_to_grid(encrypted_text, max_key_length);
     let pln_block = string_to_grid(plaintext, max_key_length);
 
     let enc_columns: Vec<String> = transpose(&enc_block).iter().map(|col| col.iter().collect()).collect();
     let pln_columns: Vec<String> = transpose(&pln_block).iter().map(|col| col.iter().collect()).collect();
 
-    let enc_rows = enc_block.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<String>>();
-    let pln_rows = pln_block.iter().map(|row| row.iter().collect::<String>()).collect::<Vec<String>>();
-
-    for i in 0..enc_columns.len() {
-        println!("Code: {} Line: {} Score: {:?} ",enc_columns[i],i + 1, substitution_cipher_score(&enc_columns[i], &pln_columns[i]));
-    }   
+    let scores = enc_columns.iter().zip(&pln_columns).map(|(enc, pln)| substitution_cipher_score(enc, pln)).collect();
     let alphabet = ALPHABET;
-    let mut new_table = create_decipher_grid(&alphabet, max_key_length);
+    let mut new_table = create_decipher_grid(alphabet, max_key_length);
 
     for column in 0..pln_columns.len(){
         let enc_col_sub = &enc_columns[column];
@@ -169,21 +188,16 @@ pub fn keyless(
         }
 
     }
-    println!("{}", pretty_grid(&new_table));
-    print_grid(&new_table);
-
-    let decod = new_decode(&new_table, encrypted_text);
-    println!("{}", encrypted_text);
-    println!("{}", decod);
 
+    let decoded = new_decode(&new_table, encrypted_text);
+    KeylessReport { columns: enc_columns, scores, table: new_table, decoded }
 }
 
 fn create_decipher_grid(key: &str, n: usize) -> Vec<Vec<char>> {
     let mut grid = vec![key.chars().collect()];
 
     for _ in 0..n {
-        let mut row = vec![' '; key.len()];
-        grid.push(row);
+        grid.push(vec![' '; key.len()]);
     }
 
     grid
@@ -209,12 +223,11 @@ fn string_to_grid(s: &str, n: usize) -> Vec<Vec<char>> {
     grid
 }
 
-fn transpose(grid: &Vec<Vec<char>>) -> Vec<Vec<char>> {
+fn transpose(grid: &[Vec<char>]) -> Vec<Vec<char>> {
     if grid.is_empty() {
         return vec![];
     }
 
-    let num_rows = grid.len();
     let num_cols = grid[0].len();
     let mut transposed: Vec<Vec<char>> = vec![vec![]; num_cols];
 
@@ -227,7 +240,8 @@ fn transpose(grid: &Vec<Vec<char>>) -> Vec<Vec<char>> {
     transposed
 }
 
-fn pretty_grid(grid: &Vec<Vec<char>>) -> String {
+/// A tableau as a text table, the plain alphabet on top and numbered rows below it.
+pub fn pretty_grid(grid: &[Vec<char>]) -> String {
     let mut result = String::new();
 
     // Print the header row
@@ -264,23 +278,13 @@ fn pretty_grid(grid: &Vec<Vec<char>>) -> String {
     result
 }
 
-fn print_grid(grid: &Vec<Vec<char>>) {
-    for row in grid {
-        for ch in row {
-            if *ch == ' ' {
-                print!("_");
-            } else {
-                print!("{}", ch);
-            }
-        }
-        println!();
-    }
-}
-
 pub fn new_decode (
-    grid: &Vec<Vec<char>>,
+    grid: &[Vec<char>],
     encrypted_text: &str,
 ) -> String {
+    if grid.len() < 2 {
+        return String::new();
+    }
     let encrypted_chars: Vec<char> = encrypted_text.to_uppercase().chars().collect();
     let mut decrypted_chars: Vec<char> = Vec::with_capacity(encrypted_chars.len());
     for (index, &encrypted_char) in encrypted_chars.iter().enumerate() {
@@ -294,3 +298,52 @@ pub fn new_decode (
     }
     decrypted_chars.into_iter().collect()
 }
+
+pub fn try_keyed_alphabet(keyword: &str) -> Result<Vec<char>> {
+    check_key("Key 1", keyword, &Alphabet::standard())?;
+    Ok(keyed_alphabet(keyword))
+}
+
+/// A table needs a second keyword to give it any cipher rows.
+pub fn try_generate_vigenere_table(keyword1: &str, keyword2: &str) -> Result<Vec<Vec<char>>> {
+    check_key("Key 1", keyword1, &Alphabet::standard())?;
+    require_key("Key 2", keyword2, &Alphabet::standard())?;
+    Ok(generate_vigenere_table(keyword1, keyword2))
+}
+
+pub fn try_vig2table(keyword1: &str, keyword2: &str) -> Result<Vec<Vec<char>>> {
+    try_generate_vigenere_table(keyword1, keyword2)
+}
+
+pub fn try_vig1table(keyword2: &str) -> Result<Vec<Vec<char>>> {
+    try_generate_vigenere_table("", keyword2)
+}
+
+pub fn try_format_vigenere_table(table: &[Vec<char>]) -> Result<String> {
+    check_tableau(table)?;
+    Ok(format_vigenere_table(table))
+}
+
+pub fn try_every_nth_letter(s: &str, n: usize) -> Result<String> {
+    check_range("Step", n, 1, s.chars().count().max(1))?;
+    Ok(every_nth_letter(s, n))
+}
+
+/// The plaintext must fill every one of the `max_key_length` columns and the encrypted
+/// text must be at least as long, or the columns cannot be lined up.
+pub fn try_keyless(encrypted_text: &str, plaintext: &str, max_key_length: usize) -> Result<KeylessReport> {
+    let (encrypted, plain) = (encrypted_text.chars().count(), plaintext.chars().count());
+    if plain == 0 {
+        return Err(KryptonError::EmptyText("Plaintext"));
+    }
+    check_range("Max Key Length", max_key_length, 1, plain)?;
+    if encrypted < plain {
+        return Err(KryptonError::TooShort { input: "Encrypted", needed: plain, found: encrypted });
+    }
+    Ok(keyless(encrypted_text, plaintext, max_key_length))
+}
+
+pub fn try_new_decode(grid: &[Vec<char>], encrypted_text: &str) -> Result<String> {
+    check_tableau(grid)?;
+    Ok(new_decode(grid, encrypted_text))
+}
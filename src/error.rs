@@ -0,0 +1,84 @@
+use std::fmt;
+
+use crate::alphabet::Alphabet;
+
+/// Everything that can go wrong with the inputs to a cipher, solver or statistic. Each
+/// variant names the input at fault the way a front end labels it, such as `Key 2`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KryptonError {
+    /// A text with none of the alphabet's symbols in it.
+    EmptyText(&'static str),
+    /// A key that needs at least one symbol of the alphabet.
+    EmptyKey(&'static str),
+    /// A key character outside the alphabet.
+    NotInAlphabet { input: &'static str, symbol: char },
+    /// Fewer symbols than the operation needs.
+    TooShort { input: &'static str, needed: usize, found: usize },
+    /// A number outside the range it must lie in.
+    OutOfRange { input: &'static str, value: usize, min: usize, max: usize },
+    /// Two texts that must be the same length are not.
+    LengthMismatch { left: usize, right: usize },
+    /// A malformed key, alphabet, table or file, with what is wrong with it.
+    Invalid { input: &'static str, reason: String },
+    /// A file or directory that could not be read or written.
+    Io(String),
+}
+
+pub type Result<T> = std::result::Result<T, KryptonError>;
+
+impl fmt::Display for KryptonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KryptonError::EmptyText(input) => write!(f, "{}: has no symbols of the alphabet", input),
+            KryptonError::EmptyKey(input) => write!(f, "{} is empty", input),
+            KryptonError::NotInAlphabet { input, symbol } => write!(f, "{}: '{}' is not in the alphabet", input, symbol),
+            KryptonError::TooShort { input, needed, found } => write!(f, "{}: needs {} symbols, has {}", input, needed, found),
+            KryptonError::OutOfRange { input, value, min, max } => write!(f, "{}: {} is outside {}..={}", input, value, min, max),
+            KryptonError::LengthMismatch { left, right } => write!(f, "Texts differ in length: {} and {}", left, right),
+            KryptonError::Invalid { input, reason } => write!(f, "{}: {}", input, reason),
+            KryptonError::Io(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for KryptonError {}
+
+impl From<std::io::Error> for KryptonError {
+    fn from(error: std::io::Error) -> Self {
+        KryptonError::Io(error.to_string())
+    }
+}
+
+/// `text` must have at least one symbol of `alphabet`.
+pub(crate) fn require_text(input: &'static str, text: &str, alphabet: &Alphabet) -> Result<()> {
+    if text.chars().any(|c| alphabet.contains(c)) {
+        Ok(())
+    } else {
+        Err(KryptonError::EmptyText(input))
+    }
+}
+
+/// Every character of `key` apart from whitespace must be in `alphabet`. An empty key passes.
+pub(crate) fn check_key(input: &'static str, key: &str, alphabet: &Alphabet) -> Result<()> {
+    match key.chars().find(|&c| !c.is_whitespace() && !alphabet.contains(c)) {
+        Some(symbol) => Err(KryptonError::NotInAlphabet { input, symbol }),
+        None => Ok(()),
+    }
+}
+
+/// `check_key` for a key that must not be empty either.
+pub(crate) fn require_key(input: &'static str, key: &str, alphabet: &Alphabet) -> Result<()> {
+    check_key(input, key, alphabet)?;
+    if key.chars().all(char::is_whitespace) {
+        return Err(KryptonError::EmptyKey(input));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_range(input: &'static str, value: usize, min: usize, max: usize) -> Result<()> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(KryptonError::OutOfRange { input, value, min, max })
+    }
+}
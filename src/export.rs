@@ -0,0 +1,361 @@
+use std::{fs, path::Path};
+
+use itertools::Itertools;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::{
+    alphabet::Alphabet,
+    analysis::{try_analyze, AnalysisResult, KasiskiRepeat, PeriodIoc},
+    anneal::KeyPair,
+    autokey::AutokeyCandidate,
+    classify::cipher_families,
+    constraints::{ConstraintReport, Outcome},
+    crib::CribMatch,
+    decipher::KeylessReport,
+    error::{KryptonError, Result},
+    key_recovery::RecoveredKey,
+    running_key::RunningKeyCandidate,
+    significance::{Significance, SignificanceConfig},
+    transposition::{ColumnarCandidate, RotationCandidate},
+};
+
+/// A result that can be written as one row of a CSV table. Every value of a type has the
+/// same columns, so a list of them lines up in a spreadsheet.
+pub trait Record {
+    fn headers() -> Vec<String>;
+    fn fields(&self) -> Vec<String>;
+}
+
+pub fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    serde_json::to_string_pretty(value).map_err(|e| KryptonError::Invalid { input: "JSON", reason: e.to_string() })
+}
+
+/// A heading row followed by one row per record.
+pub fn to_csv<T: Record>(records: &[T]) -> Result<String> {
+    write_csv(T::headers(), records.iter().map(Record::fields))
+}
+
+/// A tableau, such as one from `vig2table` or `KeylessReport::table`, one cell per symbol.
+pub fn grid_to_csv(grid: &[Vec<char>]) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(Vec::new());
+    for row in grid {
+        writer.write_record(row.iter().map(|c| c.to_string())).map_err(csv_error)?;
+    }
+    finish_csv(writer)
+}
+
+/// A name from a fixed list, such as a cipher family or a statistic. Fields spelled with
+/// this alias are not borrowed from the input by serde, so `known_name` can read them.
+pub type StaticName = &'static str;
+
+/// Reads a string back as the one of `names` it equals, for fields that hold a fixed
+/// `&'static str` such as a cipher family or a statistic.
+pub(crate) fn known_name<'de, D: Deserializer<'de>>(deserializer: D, names: &[&'static str]) -> std::result::Result<&'static str, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    names.iter().find(|&&known| known == name).copied().ok_or_else(|| de::Error::custom(format!("unknown name '{}'", name)))
+}
+
+fn write_csv(headers: Vec<String>, rows: impl Iterator<Item = Vec<String>>) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(headers).map_err(csv_error)?;
+    for row in rows {
+        writer.write_record(row).map_err(csv_error)?;
+    }
+    finish_csv(writer)
+}
+
+fn finish_csv(writer: csv::Writer<Vec<u8>>) -> Result<String> {
+    let bytes = writer.into_inner().map_err(|e| KryptonError::Io(e.error().to_string()))?;
+    String::from_utf8(bytes).map_err(|e| KryptonError::Invalid { input: "CSV", reason: e.to_string() })
+}
+
+fn csv_error(error: csv::Error) -> KryptonError {
+    KryptonError::Invalid { input: "CSV", reason: error.to_string() }
+}
+
+fn headers(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+impl Record for AnalysisResult {
+    /// The statistics of `statistics()`, the key lengths each test points to, the heaviest
+    /// Kasiski factors and one score per cipher family in the order of `cipher_families()`.
+    fn headers() -> Vec<String> {
+        let mut names = headers(&["FRIEDMAN KEY LENGTH", "BEST PHI PERIOD", "KEY ELIMINATION LENGTH", "KEY ELIMINATION KEY", "KASISKI TOP FACTORS"]);
+        names.extend(AnalysisResult::STATISTICS.iter().map(|name| name.to_string()));
+        names.extend(cipher_families().iter().map(|family| family.to_uppercase()));
+        names
+    }
+
+    fn fields(&self) -> Vec<String> {
+        let mut fields = vec![
+            self.friedman.0.to_string(),
+            self.phi.0.to_string(),
+            self.key_elim.0.to_string(),
+            self.key_elim.2.clone(),
+            self.kasiski.top(4).iter().join(" "),
+        ];
+        fields.extend(self.statistics().iter().map(|(_, value)| value.to_string()));
+        fields.extend(cipher_families().iter().map(|family| {
+            self.cipher_types.iter().find(|guess| guess.family == *family).map_or(String::new(), |guess| guess.score.to_string())
+        }));
+        fields
+    }
+}
+
+impl Record for KasiskiRepeat {
+    fn headers() -> Vec<String> {
+        headers(&["SEQUENCE", "POSITIONS", "DISTANCES"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.sequence.clone(), self.positions.iter().join(" "), self.distances.iter().join(" ")]
+    }
+}
+
+impl Record for PeriodIoc {
+    fn headers() -> Vec<String> {
+        headers(&["PERIOD", "AVERAGE", "COLUMNS"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.period.to_string(), self.average.to_string(), self.columns.iter().join(" ")]
+    }
+}
+
+impl Record for Significance {
+    fn headers() -> Vec<String> {
+        headers(&["STATISTIC", "VALUE", "SHUFFLED MEAN", "SHUFFLED Z", "SHUFFLED P", "ENGLISH MEAN", "ENGLISH Z", "ENGLISH P"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        let mut fields = vec![
+            self.statistic.to_string(),
+            self.value.to_string(),
+            self.shuffled.mean.to_string(),
+            self.shuffled.z.to_string(),
+            self.shuffled.p.to_string(),
+        ];
+        match &self.english {
+            Some(english) => fields.extend([english.mean.to_string(), english.z.to_string(), english.p.to_string()]),
+            None => fields.extend([String::new(), String::new(), String::new()]),
+        }
+        fields
+    }
+}
+
+impl Record for KeylessReport {
+    fn headers() -> Vec<String> {
+        headers(&["ALPHABETS", "COLUMNS", "SCORES", "DECODED"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        let scores = self.scores.iter().map(|score| score.map_or("-".to_string(), |s| s.to_string())).join(" ");
+        vec![self.alphabets(), self.columns.join(" "), scores, self.decoded.clone()]
+    }
+}
+
+impl Record for RecoveredKey {
+    fn headers() -> Vec<String> {
+        headers(&["FAMILY", "KEY", "FITNESS", "CONFIDENCE", "PLAINTEXT"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.family.name().to_string(),
+            self.key.clone(),
+            self.fitness.to_string(),
+            self.confidence.iter().join(" "),
+            self.plaintext.clone(),
+        ]
+    }
+}
+
+impl Record for AutokeyCandidate {
+    fn headers() -> Vec<String> {
+        headers(&["FAMILY", "MODE", "PRIMER", "SCORE", "PLAINTEXT"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.family.name().to_string(), self.mode.name().to_string(), self.primer.clone(), self.score.to_string(), self.plaintext.clone()]
+    }
+}
+
+impl Record for ColumnarCandidate {
+    fn headers() -> Vec<String> {
+        headers(&["WIDTH", "KEY", "ORDER", "FITNESS", "PLAINTEXT"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.width.to_string(), self.key.clone(), self.order.iter().join(" "), self.fitness.to_string(), self.plaintext.clone()]
+    }
+}
+
+impl Record for RotationCandidate {
+    fn headers() -> Vec<String> {
+        headers(&["WIDTH 1", "WIDTH 2", "ROTATION", "CRIB MATCHES", "FITNESS", "PLAINTEXT"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.width1.to_string(),
+            self.width2.to_string(),
+            self.rotation.name().to_string(),
+            self.crib_matches.to_string(),
+            self.fitness.to_string(),
+            self.plaintext.clone(),
+        ]
+    }
+}
+
+impl Record for RunningKeyCandidate {
+    fn headers() -> Vec<String> {
+        headers(&["SOURCE", "OFFSET", "FAMILY", "CRIB MATCHES", "FITNESS", "KEY", "PLAINTEXT"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.source.clone(),
+            self.offset.to_string(),
+            self.family.name().to_string(),
+            self.crib_matches.to_string(),
+            self.fitness.to_string(),
+            self.key.clone(),
+            self.plaintext.clone(),
+        ]
+    }
+}
+
+impl Record for KeyPair {
+    fn headers() -> Vec<String> {
+        headers(&["KEY 1", "KEY 2", "SCORE", "PLAINTEXT"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![self.keyword1.clone(), self.keyword2.clone(), self.score.to_string(), self.plaintext.clone()]
+    }
+}
+
+impl Record for CribMatch {
+    fn headers() -> Vec<String> {
+        headers(&["OFFSET", "FAMILY", "TABLEAU", "FRAGMENT", "PERIOD", "PERIODICITY", "REPEAT", "FITNESS", "SCORE"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.offset.to_string(),
+            self.family.name().to_string(),
+            self.keyword.clone(),
+            self.fragment.clone(),
+            self.period.to_string(),
+            self.periodicity.to_string(),
+            self.repeat.clone(),
+            self.fitness.to_string(),
+            self.score.to_string(),
+        ]
+    }
+}
+
+impl Record for ConstraintReport {
+    fn headers() -> Vec<String> {
+        headers(&["HYPOTHESIS", "PERIOD", "CONSISTENT", "KEY", "PLAIN ALPHABET", "CIPHER ALPHABET", "CHECKED", "CONTRADICTION", "PLAINTEXT"])
+    }
+
+    fn fields(&self) -> Vec<String> {
+        let mut fields = vec![self.hypothesis.name(), self.period.to_string()];
+        match &self.outcome {
+            Outcome::Consistent { key, plain_alphabet, cipher_alphabet, plaintext, checked } => fields.extend([
+                "true".to_string(),
+                key.clone(),
+                plain_alphabet.clone(),
+                cipher_alphabet.clone(),
+                checked.to_string(),
+                String::new(),
+                plaintext.clone(),
+            ]),
+            Outcome::Contradicted(c) => fields.extend(["false".to_string(), String::new(), String::new(), String::new(), String::new(), c.to_string(), String::new()]),
+        }
+        fields
+    }
+}
+
+/// The analysis of one file of a batch, or why it could not be analyzed.
+#[derive(Serialize)]
+pub struct BatchEntry {
+    pub file: String,
+    pub analysis: Option<AnalysisResult>,
+    pub error: Option<String>,
+}
+
+impl Record for BatchEntry {
+    fn headers() -> Vec<String> {
+        let mut names = headers(&["FILE", "ERROR"]);
+        names.extend(AnalysisResult::headers());
+        names
+    }
+
+    fn fields(&self) -> Vec<String> {
+        let mut fields = vec![self.file.clone(), self.error.clone().unwrap_or_default()];
+        match &self.analysis {
+            Some(analysis) => fields.extend(analysis.fields()),
+            None => fields.resize(fields.len() + AnalysisResult::headers().len(), String::new()),
+        }
+        fields
+    }
+}
+
+/// Analyzes every file in `dir` in name order against the same known plaintext and settings.
+/// A file that cannot be read or analyzed gets an entry with the error, so one bad file does
+/// not lose the rest of the report.
+pub fn analyze_directory(
+    dir: &Path,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+    config: &SignificanceConfig,
+) -> Result<Vec<BatchEntry>> {
+    let read_error = |e: std::io::Error| KryptonError::Io(format!("Could not read {}: {}", dir.display(), e));
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).map_err(read_error)? {
+        let path = entry.map_err(read_error)?.path();
+        if path.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths
+        .iter()
+        .map(|path| {
+            let file = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let result = fs::read_to_string(path)
+                .map_err(KryptonError::from)
+                .and_then(|text| try_analyze(&text, plaintext, max_key_length, excluded_factors, alphabet, config));
+            match result {
+                Ok(analysis) => BatchEntry { file, analysis: Some(analysis), error: None },
+                Err(e) => BatchEntry { file, analysis: None, error: Some(e.to_string()) },
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::K2;
+
+    #[test]
+    fn analysis_json_reads_back_unchanged() {
+        let config = SignificanceConfig { trials: 5, seed: 3, english: true };
+        let analysis = try_analyze(K2, "", 12, &[], &Alphabet::standard(), &config).unwrap();
+        let json = to_json(&analysis).unwrap();
+        let read: AnalysisResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(to_json(&read).unwrap(), json);
+
+        let csv = to_csv(&[analysis]).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].split(',').count(), AnalysisResult::headers().len());
+    }
+}
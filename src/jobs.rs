@@ -0,0 +1,162 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::error::{KryptonError, Result};
+
+/// Lets a long search say how far it has got, offer its best candidate so far and notice
+/// when it should stop. A cancelled search returns what it has found up to then.
+pub trait Monitor: Sync {
+    fn progress(&self, done: usize, total: usize);
+    fn best(&self, candidate: String);
+    fn cancelled(&self) -> bool;
+}
+
+/// The monitor of a search run directly, which nobody watches or cancels.
+pub struct Unmonitored;
+
+impl Monitor for Unmonitored {
+    fn progress(&self, _done: usize, _total: usize) {}
+
+    fn best(&self, _candidate: String) {}
+
+    fn cancelled(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Default)]
+struct Status {
+    done: usize,
+    total: usize,
+    best: Option<String>,
+    elapsed: Option<Duration>,
+}
+
+#[derive(Default)]
+struct Tracker {
+    status: Mutex<Status>,
+    cancel: AtomicBool,
+}
+
+impl Tracker {
+    /// The status even if a search panicked while holding it, so the job can still report.
+    fn status(&self) -> MutexGuard<'_, Status> {
+        self.status.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Monitor for Tracker {
+    fn progress(&self, done: usize, total: usize) {
+        let mut status = self.status();
+        status.done = done;
+        status.total = total;
+    }
+
+    fn best(&self, candidate: String) {
+        let mut status = self.status();
+        status.best = Some(candidate);
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
+/// A search running on a worker thread, which the GUI polls every frame.
+pub struct Job<T> {
+    pub name: String,
+    started: Instant,
+    tracker: Arc<Tracker>,
+    handle: JoinHandle<T>,
+}
+
+impl<T: Send + 'static> Job<T> {
+    /// Starts `work` on a new thread, handing it the job's monitor.
+    pub fn spawn(name: impl Into<String>, work: impl FnOnce(&dyn Monitor) -> T + Send + 'static) -> Job<T> {
+        let started = Instant::now();
+        let tracker = Arc::new(Tracker::default());
+        let worker = Arc::clone(&tracker);
+        let handle = thread::spawn(move || {
+            let result = work(worker.as_ref());
+            worker.status().elapsed = Some(started.elapsed());
+            result
+        });
+        Job { name: name.into(), started, tracker, handle }
+    }
+
+    /// Share of the work done, from 0 to 1, or `None` before the search has said.
+    pub fn progress(&self) -> Option<f64> {
+        let status = self.tracker.status();
+        (status.total > 0).then(|| status.done as f64 / status.total as f64)
+    }
+
+    /// Time since the job started, or until it finished.
+    pub fn elapsed(&self) -> Duration {
+        self.tracker.status().elapsed.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    pub fn best(&self) -> Option<String> {
+        self.tracker.status().best.clone()
+    }
+
+    pub fn cancel(&self) {
+        self.tracker.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.tracker.cancelled()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the result, which is immediate once `is_finished` is true.
+    pub fn join(self) -> Result<T> {
+        let name = self.name;
+        self.handle.join().map_err(|_| KryptonError::Invalid { input: "Job", reason: format!("{} stopped with an internal error", name) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_cancelled_job_stops_and_joins() {
+        let job = Job::spawn("Loop", |monitor| {
+            let mut done = 0;
+            while !monitor.cancelled() {
+                done += 1;
+                monitor.progress(done.min(99), 100);
+                monitor.best(format!("Step {}", done));
+                thread::sleep(Duration::from_millis(1));
+            }
+            done
+        });
+        while job.progress().is_none() || job.best().is_none() {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(job.progress().is_some_and(|p| p > 0.0 && p < 1.0));
+        assert!(!job.is_finished() && !job.is_cancelled());
+
+        job.cancel();
+        assert!(job.is_cancelled());
+        let best = job.best();
+        let done = job.join().unwrap();
+        assert!(done > 0);
+        assert!(best.is_some_and(|best| best.starts_with("Step ")));
+    }
+
+    #[test]
+    fn a_panicking_job_reports_an_error() {
+        let job: Job<()> = Job::spawn("Broken", |_| panic!("broken search"));
+        assert!(matches!(job.join(), Err(KryptonError::Invalid { input: "Job", .. })));
+    }
+}
@@ -0,0 +1,156 @@
+use serde::Serialize;
+
+use crate::{
+    alphabet::Alphabet,
+    analysis::{chi_squared_score, ENGLISH_FREQUENCIES},
+    jobs::{Monitor, Unmonitored},
+    ngram::english_fitness,
+    toolkit::{periodic_decrypt_in, Family},
+};
+
+#[derive(Serialize)]
+pub struct RecoveredKey {
+    pub family: Family,
+    pub key: String,
+    /// How clearly each key letter beat the runner-up shift for its column, from 0 to 1.
+    pub confidence: Vec<f64>,
+    pub plaintext: String,
+    pub fitness: f64,
+}
+
+/// Most passes of mutual IoC refinement before giving up on the shifts settling.
+const REFINE_PASSES: usize = 10;
+
+fn letter_counts(column: &[usize], size: usize) -> Vec<f64> {
+    let mut counts = vec![0.0; size];
+    for &c in column {
+        counts[c] += 1.0;
+    }
+    counts
+}
+
+/// Mutual index of coincidence of two letter distributions.
+fn mutual_ioc(a: &[f64], b: &[f64]) -> f64 {
+    let (total_a, total_b) = (a.iter().sum::<f64>(), b.iter().sum::<f64>());
+    if total_a == 0.0 || total_b == 0.0 {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>() / (total_a * total_b)
+}
+
+/// Recovers a periodic key from ciphertext alone. Every column first takes the shift whose
+/// decryption has the lowest chi-squared against English; then each column in turn is moved
+/// to the shift whose decryption best matches the other columns and English by mutual IoC,
+/// until no column moves. `key1` keys the tableau alphabet as in `vig2table` (straight when empty).
+pub fn recover_key(ciphertext: &str, period: usize, family: Family, key1: &str) -> RecoveredKey {
+    recover_key_in(&Alphabet::standard().keyed(key1), ciphertext, period, family)
+}
+
+/// `recover_key` over an already keyed `alphabet`. Symbols other than A to Z count as never
+/// appearing in English.
+pub fn recover_key_in(alphabet: &Alphabet, ciphertext: &str, period: usize, family: Family) -> RecoveredKey {
+    let cipher = alphabet.indices(ciphertext);
+    let size = alphabet.len();
+    let english: Vec<f64> = alphabet
+        .symbols()
+        .iter()
+        .map(|c| if c.is_ascii_uppercase() { ENGLISH_FREQUENCIES[(*c as u8 - b'A') as usize] } else { 0.0 })
+        .collect();
+    let period = period.clamp(1, cipher.len().max(1));
+    let columns: Vec<Vec<usize>> = (0..period).map(|i| cipher.iter().skip(i).step_by(period).copied().collect()).collect();
+    let decrypt = |column: &[usize], k: usize| -> Vec<usize> { column.iter().map(|&c| family.decrypt_index_in(c, k, size)).collect() };
+
+    let mut shifts: Vec<usize> = columns
+        .iter()
+        .map(|column| {
+            (0..size)
+                .map(|k| {
+                    let text = alphabet.text(&decrypt(column, k));
+                    (k, chi_squared_score(&text))
+                })
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(k, _)| k)
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut scores = vec![vec![0.0; size]; period];
+    for pass in 0..=REFINE_PASSES {
+        let mut moved = false;
+        for j in 0..period {
+            // English counts for one column keep the shifts anchored, even at period 1.
+            let mut rest: Vec<f64> = english.iter().map(|f| f * columns[j].len() as f64).collect();
+            for (i, column) in columns.iter().enumerate().filter(|&(i, _)| i != j) {
+                for (total, count) in rest.iter_mut().zip(letter_counts(&decrypt(column, shifts[i]), size)) {
+                    *total += count;
+                }
+            }
+            for (k, score) in scores[j].iter_mut().enumerate() {
+                *score = mutual_ioc(&letter_counts(&decrypt(&columns[j], k), size), &rest);
+            }
+            let best = (0..size).max_by(|&a, &b| scores[j][a].total_cmp(&scores[j][b])).unwrap_or(0);
+            if pass < REFINE_PASSES && scores[j][best] > scores[j][shifts[j]] {
+                shifts[j] = best;
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    let confidence = shifts
+        .iter()
+        .zip(&scores)
+        .map(|(&k, score)| {
+            let runner_up = (0..size).filter(|&s| s != k).map(|s| score[s]).fold(0.0, f64::max);
+            if score[k] > 0.0 {
+                ((score[k] - runner_up) / score[k]).max(0.0)
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    let key = alphabet.text(&shifts);
+    let plaintext = periodic_decrypt_in(alphabet, ciphertext, family, &key);
+    RecoveredKey { family, fitness: english_fitness(&plaintext), key, confidence, plaintext }
+}
+
+/// `recover_key` for every family, most English-like decryption first. Variant Beaufort
+/// with a key decrypts like Vigenere with its inverse, so those two always tie.
+pub fn recover_keys(ciphertext: &str, period: usize, key1: &str) -> Vec<RecoveredKey> {
+    recover_keys_in(&Alphabet::standard().keyed(key1), ciphertext, period)
+}
+
+pub fn recover_keys_in(alphabet: &Alphabet, ciphertext: &str, period: usize) -> Vec<RecoveredKey> {
+    recover_keys_monitored(alphabet, ciphertext, period, &Unmonitored)
+}
+
+/// `recover_keys_in` reporting each family as it is done, and returning the keys of the
+/// families done so far when cancelled.
+pub fn recover_keys_monitored(alphabet: &Alphabet, ciphertext: &str, period: usize, monitor: &dyn Monitor) -> Vec<RecoveredKey> {
+    let mut keys = Vec::new();
+    for (done, &family) in Family::ALL.iter().enumerate() {
+        if monitor.cancelled() {
+            break;
+        }
+        monitor.progress(done, Family::ALL.len());
+        keys.push(recover_key_in(alphabet, ciphertext, period, family));
+    }
+    keys.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::K2;
+
+    #[test]
+    fn recovers_the_k2_key() {
+        let keys = recover_keys(K2, 8, "KRYPTOS");
+        assert_eq!(keys[0].key, "ABSCISSA");
+        assert_eq!(keys[0].family, Family::Vigenere);
+        assert!(keys[0].plaintext.starts_with("ITWASTOTALLYINVISIBLE"), "{}", keys[0].plaintext);
+    }
+}
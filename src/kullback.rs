@@ -0,0 +1,96 @@
+use crate::{
+    alphabet::Alphabet,
+    analysis::{column_ioc, PeriodIoc},
+    error::{check_range, KryptonError, Result},
+    jobs::{Monitor, Unmonitored},
+};
+
+/// Scaled IoC of random text and of English, the bottom and top of the graph.
+const GRAPH_RANGE: (f64, f64) = (1.0, 1.77);
+/// Scaled IoC above which a width is listed under the graph as a likely key length.
+const NOTABLE: f64 = 1.5;
+/// Rows of the graph.
+const HEIGHT: usize = 10;
+
+/// Writes `letters` in rows of `n` and reads them off as `n` columns. A short last row is
+/// left out so every column has the same length. `None` when there is no full row.
+pub fn split_and_transform(letters: &[usize], n: usize) -> Option<Vec<Vec<usize>>> {
+    if n == 0 || letters.len() < n {
+        return None;
+    }
+    let rows: Vec<&[usize]> = letters.chunks_exact(n).collect();
+    Some((0..n).map(|i| rows.iter().map(|row| row[i]).collect()).collect())
+}
+
+/// Kullback's test: the IoC of every column of the text written out at every width from 1
+/// to `max_width`, scaled by the alphabet size so random text sits near 1.0 and English
+/// near 1.73. Widths without two full rows have no columns and average 0.
+pub fn kullback(encrypted_text: &str, alphabet: &Alphabet, max_width: usize) -> Vec<PeriodIoc> {
+    kullback_monitored(encrypted_text, alphabet, max_width, &Unmonitored)
+}
+
+/// `kullback` reporting each width as it is done, and returning the widths done so far
+/// when cancelled.
+pub fn kullback_monitored(encrypted_text: &str, alphabet: &Alphabet, max_width: usize, monitor: &dyn Monitor) -> Vec<PeriodIoc> {
+    let letters = alphabet.indices(encrypted_text);
+    let mut widths = Vec::with_capacity(max_width);
+    for width in 1..=max_width {
+        if monitor.cancelled() {
+            break;
+        }
+        monitor.progress(width - 1, max_width);
+        let columns: Vec<f64> = match split_and_transform(&letters, width) {
+            Some(columns) if letters.len() >= 2 * width => columns.iter().map(|column| column_ioc(column, alphabet.len())).collect(),
+            _ => Vec::new(),
+        };
+        let average = if columns.is_empty() { 0.0 } else { columns.iter().sum::<f64>() / columns.len() as f64 };
+        widths.push(PeriodIoc { period: width, average, columns });
+    }
+    widths
+}
+
+/// A bar per width from `kullback`, rising from random text to English, with a ruler
+/// marking every fifth width and the widths above `NOTABLE` listed underneath.
+pub fn kullback_graph(widths: &[PeriodIoc]) -> String {
+    let heights: Vec<usize> = widths
+        .iter()
+        .map(|width| {
+            let share = (width.average.clamp(GRAPH_RANGE.0, GRAPH_RANGE.1) - GRAPH_RANGE.0) / (GRAPH_RANGE.1 - GRAPH_RANGE.0);
+            (share * HEIGHT as f64).round() as usize
+        })
+        .collect();
+
+    let mut graph = String::new();
+    for row in (0..HEIGHT).rev() {
+        for &height in &heights {
+            graph.push(match height {
+                h if h <= row => ' ',
+                h if h > HEIGHT * 8 / 10 => '█',
+                _ => '▒',
+            });
+        }
+        graph.push('\n');
+    }
+    graph.extend(widths.iter().map(|width| if width.period % 5 == 0 { '+' } else { '-' }));
+    graph.push('\n');
+
+    let notable: Vec<String> = widths.iter().filter(|width| width.average > NOTABLE).map(|width| width.period.to_string()).collect();
+    graph.push_str(&format!("Widths above {:.2}: {}", NOTABLE, notable.join(", ")));
+    graph
+}
+
+// Checked versions of the functions above, for input typed in by the user.
+
+/// The text needs two full rows at the widest width.
+pub fn try_kullback(encrypted_text: &str, alphabet: &Alphabet, max_width: usize) -> Result<Vec<PeriodIoc>> {
+    try_kullback_monitored(encrypted_text, alphabet, max_width, &Unmonitored)
+}
+
+pub fn try_kullback_monitored(encrypted_text: &str, alphabet: &Alphabet, max_width: usize, monitor: &dyn Monitor) -> Result<Vec<PeriodIoc>> {
+    let found = alphabet.indices(encrypted_text).len();
+    if found < 2 {
+        return Err(KryptonError::TooShort { input: "Encrypted", needed: 2, found });
+    }
+    check_range("Max Key Length", max_width, 1, found / 2)?;
+    Ok(kullback_monitored(encrypted_text, alphabet, max_width, monitor))
+}
@@ -0,0 +1,28 @@
+//! Ciphers, solvers and statistics for classical cryptanalysis, shared by the KRYPTON
+//! window and the `krypton` command line.
+
+pub mod alphabet;
+pub mod analysis;
+pub mod anneal;
+pub mod autokey;
+pub mod cases;
+pub mod cipher;
+pub mod classify;
+pub mod compare;
+pub mod constraints;
+pub mod crib;
+pub mod crypt;
+pub mod decipher;
+pub mod error;
+pub mod export;
+pub mod jobs;
+pub mod key_recovery;
+pub mod kullback;
+pub mod ngram;
+pub mod normalize;
+pub mod running_key;
+pub mod session;
+pub mod significance;
+pub mod toolkit;
+pub mod transposition;
+pub mod vigenere;
@@ -1,59 +1,270 @@
-use std::fmt::Pointer;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use analysis::{analyze, percentage_blocks, AnalysisResult};
 use eframe::egui;
-
-mod analysis;
-
-mod crypt;
-use crypt::*;
-
-mod decipher;
-use decipher::*;
-
-mod vigenere;
 use egui::{style::HandleShape, FontId};
-use vigenere::*;
+use egui_plot::{Bar, BarChart, HLine, Plot, Points};
+use itertools::Itertools;
 
-mod toolkit;
-use toolkit::*;
+use krypton::{
+    alphabet::{Alphabet, ALPHABETS},
+    analysis::{percentage_blocks, try_analyze_monitored, AnalysisResult},
+    anneal::{anneal_keyed_vigenere_monitored, AnnealConfig},
+    cases::{all_cases, cipher_types, TestCase, CASES_DIR},
+    autokey::{autokey_crib_in, autokey_solve_in, rank},
+    cipher::{bullshark_monitored, find_cipher, registry, Cipher, ParamKind},
+    compare::{compare, Agreement, Comparison},
+    constraints::{solve_constraints_monitored, Outcome},
+    crib::{crib_drag_monitored, sort_matches, CribColumn, CribMatch},
+    error::KryptonError,
+    export::{to_csv, to_json},
+    jobs::Job,
+    key_recovery::recover_keys_monitored,
+    kullback::{kullback_graph, try_kullback_monitored},
+    normalize::try_preserve_format,
+    running_key::{load_corpus, running_key_search_monitored},
+    session::{load_session, save_session, Session, SESSION_VERSION},
+    significance::SignificanceConfig,
+    toolkit::{Autokey, Family},
+    transposition::{columnar_solve_monitored, rotating_grid_search_monitored},
+};
+
+/// What a background job hands back to the GUI when it finishes.
+enum JobResult {
+    Output(String),
+    Analysis(Box<AnalysisResult>),
+    /// Candidates for the output box, and the best key to select with its cipher.
+    Solution { text: String, cipher: &'static str, keys: Option<Vec<String>> },
+    CribMatches(Vec<CribMatch>),
+}
 
 struct MyApp {
     analysis: Option<AnalysisResult>,
     encrypted: String,
     plaintext: String,
     key_length: usize,
-    key1: String,
-    key2: String,
+    ciphers: Vec<Box<dyn Cipher>>,
+    cipher: usize,
+    keys: Vec<String>,
+    alphabet: usize,
+    custom_alphabet: String,
+    preserve_format: bool,
+    corpus_dir: String,
+    anneal: AnnealConfig,
+    excluded_factors: String,
+    significance: SignificanceConfig,
+    constraint_keywords: String,
+    crib: String,
+    crib_matches: Vec<CribMatch>,
+    crib_sort: CribColumn,
+    crib_descending: bool,
     output: String,
+    /// The last error, shown under the output until something succeeds.
+    error: Option<KryptonError>,
     terminal1: String, 
     terminal2: String, 
     terminal3: String, 
     terminal4: String, 
-    
-    
+    cases: Vec<TestCase>,
+    cases_dir: String,
+    /// Cipher type the test case browser shows, all of them when `None`.
+    case_filter: Option<String>,
+    /// Index into `cases` of the case last loaded into the buffers.
+    case: Option<usize>,
+    /// The session file that Save writes to, once opened or saved as.
+    session_path: Option<PathBuf>,
+    /// Searches running on worker threads, oldest first.
+    jobs: Vec<Job<Result<JobResult, KryptonError>>>,
 }
 
 impl Default for MyApp {
     fn default() -> Self {
+        let loaded = all_cases(Path::new(CASES_DIR));
+        let error = loaded.error();
+        let cases = loaded.cases;
         Self {
             analysis: None,
             encrypted: "ENCRYPTED".to_string(),
             plaintext: "PLAINTEXT".to_string(),
             output: "OUTPUT".to_string(),
+            error,
             key_length: Default::default(),
-            key1: String::new(),
-            key2: String::new(),
+            ciphers: registry(),
+            cipher: 0,
+            keys: Vec::new(),
+            alphabet: 0,
+            custom_alphabet: "KRYPTOSABCDEFGHIJLMNQUVWXZ".to_string(),
+            preserve_format: false,
+            corpus_dir: String::new(),
+            anneal: AnnealConfig::default(),
+            excluded_factors: "1,2,4".to_string(),
+            significance: SignificanceConfig::default(),
+            constraint_keywords: "KRYPTOS, ABSCISSA, PALIMPSEST".to_string(),
+            crib: "BERLINCLOCK".to_string(),
+            crib_matches: Vec::new(),
+            crib_sort: CribColumn::Score,
+            crib_descending: true,
             terminal1: String::new(),
             terminal2: String::new(),
             terminal3: String::new(),
             terminal4: String::new(),
+            cases,
+            cases_dir: CASES_DIR.to_string(),
+            case_filter: None,
+            case: None,
+            session_path: None,
+            jobs: Vec::new(),
+        }
+    }
+}
+
+/// Key under which eframe keeps the session file path between runs.
+const SESSION_PATH_KEY: &str = "session_path";
+
+impl MyApp {
+    fn session(&self) -> Session {
+        Session {
+            version: SESSION_VERSION,
+            encrypted: self.encrypted.clone(),
+            plaintext: self.plaintext.clone(),
+            key_length: self.key_length,
+            cipher: self.ciphers[self.cipher].name().to_string(),
+            keys: self.keys.clone(),
+            alphabet: self.alphabet,
+            custom_alphabet: self.custom_alphabet.clone(),
+            preserve_format: self.preserve_format,
+            corpus_dir: self.corpus_dir.clone(),
+            anneal: self.anneal.clone(),
+            excluded_factors: self.excluded_factors.clone(),
+            significance: self.significance.clone(),
+            constraint_keywords: self.constraint_keywords.clone(),
+            crib: self.crib.clone(),
+            crib_sort: self.crib_sort,
+            crib_descending: self.crib_descending,
+            output: self.output.clone(),
+            terminals: [self.terminal1.clone(), self.terminal2.clone(), self.terminal3.clone(), self.terminal4.clone()],
+            analysis: self.analysis.clone(),
+            crib_matches: self.crib_matches.clone(),
+            cases_dir: self.cases_dir.clone(),
+        }
+    }
+
+    /// Replaces everything on screen with `session`. A cipher this build does not have
+    /// leaves the selection as it is.
+    fn restore(&mut self, session: Session) {
+        if let Some(index) = self.ciphers.iter().position(|c| c.name() == session.cipher) {
+            self.cipher = index;
+        }
+        self.encrypted = session.encrypted;
+        self.plaintext = session.plaintext;
+        self.key_length = session.key_length;
+        self.keys = session.keys;
+        self.alphabet = session.alphabet.min(ALPHABETS.len() - 1);
+        self.custom_alphabet = session.custom_alphabet;
+        self.preserve_format = session.preserve_format;
+        self.corpus_dir = session.corpus_dir;
+        self.anneal = session.anneal;
+        self.excluded_factors = session.excluded_factors;
+        self.significance = session.significance;
+        self.constraint_keywords = session.constraint_keywords;
+        self.crib = session.crib;
+        self.crib_sort = session.crib_sort;
+        self.crib_descending = session.crib_descending;
+        self.output = session.output;
+        [self.terminal1, self.terminal2, self.terminal3, self.terminal4] = session.terminals;
+        self.analysis = session.analysis;
+        self.crib_matches = session.crib_matches;
+        self.error = None;
+        if !session.cases_dir.is_empty() && session.cases_dir != self.cases_dir {
+            self.cases_dir = session.cases_dir;
+            self.reload_cases();
+        }
+    }
+
+    fn reload_cases(&mut self) {
+        let loaded = all_cases(Path::new(&self.cases_dir));
+        self.error = loaded.error();
+        self.cases = loaded.cases;
+        self.case = None;
+    }
+
+    /// Puts a test case's ciphertext and plaintext in the buffers and, when its cipher is in
+    /// the toolkit, selects the cipher and its key.
+    fn load_case(&mut self, index: usize) {
+        let case = &self.cases[index];
+        self.encrypted = case.ciphertext.clone();
+        self.plaintext = case.plaintext.clone();
+        if let Some(cipher) = self.ciphers.iter().position(|c| c.name() == case.cipher) {
+            self.cipher = cipher;
+            self.keys = case.key.clone();
+        }
+        self.case = Some(index);
+    }
+
+    /// Applies the results of the jobs that have finished. Running jobs show their best
+    /// candidate so far in the jobs panel, so several can run without fighting over the output.
+    fn poll_jobs(&mut self) {
+        let (finished, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.jobs).into_iter().partition(|job| job.is_finished());
+        self.jobs = running;
+        for job in finished {
+            match job.join().and_then(|result| result) {
+                Ok(JobResult::Output(text)) => show_result(Ok(text), &mut self.output, &mut self.error),
+                Ok(JobResult::Analysis(analysis)) => {
+                    self.analysis = Some(*analysis);
+                    self.error = None;
+                }
+                Ok(JobResult::Solution { text, cipher, keys }) => {
+                    if let (Some(index), Some(keys)) = (self.ciphers.iter().position(|c| c.name() == cipher), keys) {
+                        self.cipher = index;
+                        self.keys = keys;
+                    }
+                    show_result(Ok(text), &mut self.output, &mut self.error);
+                }
+                Ok(JobResult::CribMatches(matches)) => {
+                    self.crib_matches = matches;
+                    sort_matches(&mut self.crib_matches, self.crib_sort, self.crib_descending);
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+
+    fn open_session(&mut self, path: &Path) {
+        match load_session(path) {
+            Ok(session) => {
+                self.restore(session);
+                self.session_path = Some(path.to_path_buf());
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    fn save_session(&mut self, path: &Path) {
+        match save_session(path, &self.session()) {
+            Ok(()) => {
+                self.session_path = Some(path.to_path_buf());
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
         }
     }
 }
 
 impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+    /// Keeps the whole session, and the file it came from, for the next start.
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.session());
+        eframe::set_value(storage, SESSION_PATH_KEY, &self.session_path);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll_jobs();
+        if !self.jobs.is_empty() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
         egui::SidePanel::left("ANALYSIS")
             .min_width(ctx.available_rect().width() * 0.25)
             .resizable(false)
@@ -63,57 +274,120 @@ impl eframe::App for MyApp {
                     ui.separator();
                     ui.add_space(32.0);
 
-                    if let Some(_) = &self.analysis{
-                        let value = &self.analysis.as_ref().unwrap();
+                    if let Some(value) = &self.analysis {
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("CHI SCORE:                 |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("CHI SCORE:                 |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.chi_score, 0.0, 10.0))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{:?}", value.chi_score)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));                          
                         });
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("MATCH SCORE:               |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("MATCH SCORE:               |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.match_score, 0.0, 100.0))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{:?}", value.match_score)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
                         });
                        
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("FRIEDMAN TEST:             |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("FRIEDMAN TEST:             |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.friedman.1, 0.0, 1.0))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{:?}", value.friedman)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
                         });
 
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("KEY ELIMINATION:           |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("KEY ELIMINATION:           |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.key_elim.1, 0.0, 0.6))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{:?}", value.key_elim)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
                         });
 
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("INCIDENCE OF COINCIDENCE:  |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
-                            ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.IOC, 0.572, 1.04))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
-                            ui.heading(egui::RichText::new(format!("{:?}", value.IOC)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("INCIDENCE OF COINCIDENCE:  |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.ioc, 0.572, 1.04))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new(format!("{:?}", value.ioc)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
                         });
 
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("AVG INDEX OF COINCIDENCE:  |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
-                            ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.phi.1, 1.0, 2.5))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("AVG INDEX OF COINCIDENCE:  |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{:?}", value.phi)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
                         });
+                        let bars: Vec<Bar> = value.ioc_curve.iter().map(|p| Bar::new(p.period as f64, p.average).width(0.6)).collect();
+                        let columns: Vec<[f64; 2]> = value.ioc_curve.iter().flat_map(|p| p.columns.iter().map(move |&c| [p.period as f64, c])).collect();
+                        Plot::new("IOC CURVE").height(200.0).allow_scroll(false).show(ui, |plot| {
+                            plot.bar_chart(BarChart::new(bars).color(egui::Color32::LIGHT_GREEN).name("Average"));
+                            plot.points(Points::new(columns).color(egui::Color32::WHITE).radius(1.5).name("Columns"));
+                            plot.hline(HLine::new(1.0).color(egui::Color32::GRAY).name("Random"));
+                            plot.hline(HLine::new(1.73).color(egui::Color32::LIGHT_YELLOW).name("English"));
+                        });
 
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("ASTER SCORE:               |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("ASTER SCORE:               |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.aster, 0.0, 100.0))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{:?}", value.aster)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
                         });
 
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("SUBSTITUTION SCORE:        |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("SUBSTITUTION SCORE:        |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.substitution_match, 0.0, 100.0))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
                             ui.heading(egui::RichText::new(format!("{:?}", value.substitution_match)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
                         });
                         ui.horizontal(|ui| {
-                            ui.heading(egui::RichText::new(format!("KASISKI EXAMINATION:       |")).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
-                            ui.heading(egui::RichText::new(format!("{:?}|", value.kasiski)).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new("QUADGRAM FITNESS:          |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(value.fitness, -9.0, -4.0))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new(format!("{:?}", value.fitness)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.heading(egui::RichText::new("ACA STATISTICS:            |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            let aca = &value.aca;
+                            ui.heading(egui::RichText::new(format!(
+                                "IC {:.0} MIC {:.0} MKA {:.0} DIC {:.0} EDI {:.0} LR {:.1} ROD {:.0} LDI {:.0} SDD {:.0}",
+                                aca.ic, aca.mic, aca.mka, aca.dic, aca.edi, aca.lr, aca.rod, aca.ldi, aca.sdd,
+                            )).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                        });
+                        for guess in &value.cipher_types {
+                            ui.horizontal(|ui| {
+                                ui.heading(egui::RichText::new(format!("{:<27}|", guess.family.to_uppercase())).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                                ui.heading(egui::RichText::new(format!("{}   |", percentage_blocks(guess.score, 0.0, 1.0))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                                ui.heading(egui::RichText::new(format!("{:.3}", guess.score)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                            });
+                        }
+                        ui.horizontal(|ui| {
+                            ui.heading(egui::RichText::new("KASISKI EXAMINATION:       |").color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                            ui.heading(egui::RichText::new(format!("{:?}|", value.kasiski.top(4))).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                        });
+                        ui.label(egui::RichText::new(
+                            value.kasiski.factors.iter().take(12).map(|(factor, weight)| format!("{}:{}", factor, weight)).collect::<Vec<String>>().join("  "),
+                        ).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                        egui::ScrollArea::vertical().id_source("KASISKI").max_height(300.0).show(ui, |ui| {
+                            egui::Grid::new("KASISKI REPEATS").striped(true).show(ui, |ui| {
+                                for heading in ["SEQUENCE", "POSITIONS", "DISTANCES"] {
+                                    ui.label(egui::RichText::new(heading).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                                }
+                                ui.end_row();
+                                for repeat in &value.kasiski.repeats {
+                                    ui.label(egui::RichText::new(&repeat.sequence).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                                    ui.label(egui::RichText::new(repeat.positions.iter().join(" ")).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                                    ui.label(egui::RichText::new(repeat.distances.iter().join(" ")).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                                    ui.end_row();
+                                }
+                            });
+                        });
+                        ui.add_space(16.0);
+                        egui::ScrollArea::vertical().id_source("SIGNIFICANCE").max_height(300.0).show(ui, |ui| {
+                            egui::Grid::new("SIGNIFICANCE").striped(true).show(ui, |ui| {
+                                for heading in ["STATISTIC", "VALUE", "SHUFFLED Z", "P", "ENGLISH Z", "P"] {
+                                    ui.label(egui::RichText::new(heading).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0)));
+                                }
+                                ui.end_row();
+                                for test in &value.significance {
+                                    let mut cells = vec![test.statistic.to_string(), format!("{:.3}", test.value), format!("{:+.2}", test.shuffled.z), format!("{:.3}", test.shuffled.p)];
+                                    if let Some(english) = &test.english {
+                                        cells.push(format!("{:+.2}", english.z));
+                                        cells.push(format!("{:.3}", english.p));
+                                    }
+                                    for cell in cells {
+                                        ui.label(egui::RichText::new(cell).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
                         });
                         
                     } 
@@ -130,20 +404,55 @@ impl eframe::App for MyApp {
                 });
 
                 ui.vertical_centered_justified( |ui | {
-                    ui.label(egui::RichText::new("Key 1").size(24.0).color(egui::Color32::LIGHT_GREEN));
-                    ui.add_sized(
-                        [100.0, 32.0],
-                        egui::TextEdit::singleline(&mut self.key1)
-                            .font(FontId::monospace(20.0))
-                            .hint_text("Key 1").text_color(egui::Color32::LIGHT_YELLOW),
-                    );
-                    ui.label(egui::RichText::new("Key 2").size(24.0).color(egui::Color32::LIGHT_GREEN));
-                    ui.add_sized(
-                        [100.0, 32.0],
-                        egui::TextEdit::singleline(&mut self.key2)
-                            .font(FontId::monospace(20.0))
-                            .hint_text("Key 2").text_color(egui::Color32::LIGHT_YELLOW),
-                    );
+                    egui::ComboBox::from_label("Cipher")
+                        .selected_text(egui::RichText::new(self.ciphers[self.cipher].name()).size(16.0))
+                        .show_ui(ui, |ui| {
+                            for (index, cipher) in self.ciphers.iter().enumerate() {
+                                ui.selectable_value(&mut self.cipher, index, egui::RichText::new(cipher.name()).size(16.0));
+                            }
+                        });
+                    egui::ComboBox::from_label("Alphabet")
+                        .selected_text(egui::RichText::new(ALPHABETS[self.alphabet]).size(16.0))
+                        .show_ui(ui, |ui| {
+                            for (index, name) in ALPHABETS.iter().enumerate() {
+                                ui.selectable_value(&mut self.alphabet, index, egui::RichText::new(*name).size(16.0));
+                            }
+                        });
+                    if self.alphabet == ALPHABETS.len() - 1 {
+                        ui.add_sized(
+                            [100.0, 32.0],
+                            egui::TextEdit::singleline(&mut self.custom_alphabet)
+                                .font(FontId::monospace(20.0))
+                                .hint_text("Symbols in order").text_color(egui::Color32::LIGHT_YELLOW),
+                        );
+                    }
+                    ui.checkbox(&mut self.preserve_format, egui::RichText::new("Preserve Case, Spacing & Punctuation").size(16.0).color(egui::Color32::LIGHT_GREEN));
+                    let params = self.ciphers[self.cipher].params();
+                    if self.keys.len() < params.len() {
+                        self.keys.resize(params.len(), String::new());
+                    }
+                    for (param, key) in params.iter().zip(self.keys.iter_mut()) {
+                        ui.label(egui::RichText::new(param.name).size(24.0).color(egui::Color32::LIGHT_GREEN));
+                        if let ParamKind::Choice(options) = param.kind {
+                            if !options.contains(&key.as_str()) {
+                                *key = options[0].to_string();
+                            }
+                            egui::ComboBox::from_id_source(param.name)
+                                .selected_text(egui::RichText::new(key.as_str()).size(16.0))
+                                .show_ui(ui, |ui| {
+                                    for option in options {
+                                        ui.selectable_value(key, option.to_string(), egui::RichText::new(*option).size(16.0));
+                                    }
+                                });
+                            continue;
+                        }
+                        ui.add_sized(
+                            [100.0, 32.0],
+                            egui::TextEdit::singleline(key)
+                                .font(FontId::monospace(20.0))
+                                .hint_text(param.description).text_color(egui::Color32::LIGHT_YELLOW),
+                        );
+                    }
                     ui.add_space(16.0);
                     ui.style_mut().spacing.slider_width = 300.0;
                     ui.style_mut().spacing.slider_rail_height = 16.0;
@@ -151,21 +460,183 @@ impl eframe::App for MyApp {
                     .prefix("Max Key Length:  ")
                     .handle_shape(HandleShape::Rect { aspect_ratio: (2.0) })
                     .text_color(egui::Color32::LIGHT_YELLOW));
-                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Encrypt Viginere 1 Key\nPlaintext, Key1 -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
-                        self.output = vigenere_one_encrypt(&self.plaintext, &self.key1);
+                    let cipher = &self.ciphers[self.cipher];
+                    let keys: Vec<&str> = self.keys.iter().map(|k| k.as_str()).collect();
+                    let alphabet = Alphabet::choice(self.alphabet, &self.custom_alphabet);
+                    let parse_key = |keys: &[&str]| cipher.parse_key(keys).and_then(|key| alphabet.clone().map(|alphabet| key.with_alphabet(alphabet)));
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new(format!("Encrypt {}\nPlaintext, Keys -> Output", cipher.name())).size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let result = parse_key(&keys).and_then(|key| {
+                            if self.preserve_format {
+                                try_preserve_format(&self.plaintext, &key.alphabet, |text| cipher.try_encrypt(text, &key))
+                            } else {
+                                cipher.try_encrypt(&self.plaintext, &key)
+                            }
+                        });
+                        show_result(result, &mut self.output, &mut self.error);
                     } 
-
-                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Encrypt Viginere 2 Key\nPlaintext, Key1, Key2 -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
-                        self.output = vigenere_two_encrypt(&self.plaintext, &self.key1, &self.key2);
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new(format!("Decrypt {}\nEncrypted, Keys -> Output", cipher.name())).size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let result = parse_key(&keys).and_then(|key| {
+                            if self.preserve_format {
+                                try_preserve_format(&self.encrypted, &key.alphabet, |text| cipher.try_decrypt(text, &key))
+                            } else {
+                                cipher.try_decrypt(&self.encrypted, &key)
+                            }
+                        });
+                        show_result(result, &mut self.output, &mut self.error);
                     } 
                     ui.add_space(16.0);
-                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Decrypt Viginere 1 Key\nEncrypted, Key1 -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
-                        self.output = vigenere_one_decrypt(&self.encrypted, &self.key1);
-                    } 
-
-                    if ui.add_sized([300.0,20.0], egui::Button::new(egui::RichText::new("Decrypt Viginere 2 Key\nEncrypted, Key1, Key2 -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
-                        self.output = vigenere_two_decrypt(&self.encrypted, &self.key1, &self.key2);
-                    }       
+                    // The searches below run as jobs on worker threads, working on copies of
+                    // the buffers taken when the button is clicked.
+                    let keyword = keys.first().copied().unwrap_or("").to_string();
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new(format!("Bullshark Analysis {}\nEncrypted, Plaintext, Keys -> Output", cipher.name())).size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let slot = cipher.period_slot();
+                        match (parse_key(&keys), slot) {
+                            (Ok(key), Some(slot)) => {
+                                let (name, encrypted, plaintext, key_length) = (cipher.name(), self.encrypted.clone(), self.plaintext.clone(), self.key_length);
+                                self.jobs.push(Job::spawn(format!("Bullshark Analysis {}", name), move |monitor| {
+                                    let cipher = find_cipher(name).ok_or_else(|| KryptonError::Invalid { input: "Cipher", reason: format!("'{}' is not in the toolkit", name) })?;
+                                    let (key, score, decrypted) = bullshark_monitored(cipher.as_ref(), &key, slot, &encrypted, &plaintext, key_length, monitor);
+                                    Ok(JobResult::Output(format!("BestScore: {}\nBest Keyword: {}\nDecrypted: {}", score, key.keyword(slot), decrypted)))
+                                }));
+                            }
+                            (Err(e), _) => self.error = Some(e),
+                            (_, None) => self.error = Some(KryptonError::Invalid { input: cipher.name(), reason: "no keyword to search".to_string() }),
+                        }
+                    }
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Recover Periodic Key\nEncrypted, Key 1, Max Key Length -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let (encrypted, key_length, keyword, alphabet) = (self.encrypted.clone(), self.key_length, keyword.clone(), alphabet.clone());
+                        self.jobs.push(Job::spawn("Recover Periodic Key", move |monitor| {
+                            Ok(JobResult::Output(recover_keys_monitored(&alphabet?.keyed(&keyword), &encrypted, key_length, monitor)
+                                .iter()
+                                .map(|r| format!("{} Key: {} Fitness: {:.3}\nConfidence: {}\n{}", r.family.name(), r.key, r.fitness, r.confidence.iter().map(|c| format!("{:.2}", c)).join(" "), r.plaintext))
+                                .collect::<Vec<String>>()
+                                .join("\n")))
+                        }));
+                    }
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Autokey Attack\nEncrypted, Plaintext, Key 1 -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let (encrypted, plaintext, key_length, keyword, alphabet) = (self.encrypted.clone(), self.plaintext.clone(), self.key_length, keyword.clone(), alphabet.clone());
+                        self.jobs.push(Job::spawn("Autokey Attack", move |monitor| {
+                            let alphabet = alphabet?.keyed(&keyword);
+                            let crib = plaintext.chars().any(|c| alphabet.index(c).is_some());
+                            let mut candidates = Vec::new();
+                            let attacks: Vec<(Family, Autokey)> = Family::ALL.into_iter().cartesian_product(Autokey::ALL).collect();
+                            for (done, &(family, mode)) in attacks.iter().enumerate() {
+                                if monitor.cancelled() {
+                                    break;
+                                }
+                                monitor.progress(done, attacks.len());
+                                if crib {
+                                    candidates.extend(autokey_crib_in(&alphabet, &encrypted, family, mode, &plaintext, key_length));
+                                } else {
+                                    candidates.extend(autokey_solve_in(&alphabet, &encrypted, family, mode, key_length));
+                                }
+                            }
+                            rank(&mut candidates);
+                            Ok(JobResult::Output(candidates
+                                .iter()
+                                .take(10)
+                                .map(|c| format!("{} {} Primer: {} Score: {:.3}\n{}", c.family.name(), c.mode.name(), c.primer, c.score, c.plaintext))
+                                .collect::<Vec<String>>()
+                                .join("\n")))
+                        }));
+                    }
+                    ui.add_space(16.0);
+                    ui.label(egui::RichText::new("Alphabet Keywords").size(24.0).color(egui::Color32::LIGHT_GREEN));
+                    ui.add_sized(
+                        [100.0, 32.0],
+                        egui::TextEdit::singleline(&mut self.constraint_keywords)
+                            .font(FontId::monospace(20.0))
+                            .hint_text("Comma separated").text_color(egui::Color32::LIGHT_YELLOW),
+                    );
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Known Plaintext Constraints\nEncrypted, Plaintext, Keywords, Max Key Length -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let keywords: Vec<String> = self.constraint_keywords.split(',').map(str::trim).filter(|k| !k.is_empty()).map(str::to_string).collect();
+                        let (encrypted, plaintext, key_length, alphabet) = (self.encrypted.clone(), self.plaintext.clone(), self.key_length, alphabet.clone());
+                        self.jobs.push(Job::spawn("Known Plaintext Constraints", move |monitor| {
+                            let keywords: Vec<&str> = keywords.iter().map(String::as_str).collect();
+                            let reports = solve_constraints_monitored(&encrypted, &plaintext, &alphabet?, &keywords, key_length, monitor)?;
+                            Ok(JobResult::Output(reports
+                                .iter()
+                                .map(|r| match &r.outcome {
+                                    Outcome::Consistent { key, plain_alphabet, cipher_alphabet, plaintext, checked } => {
+                                        format!("{} Period: {} Key: {} Checked: {}\nAlphabets: {} / {}\n{}", r.hypothesis.name(), r.period, key, checked, plain_alphabet, cipher_alphabet, plaintext)
+                                    }
+                                    Outcome::Contradicted(c) => format!("{} Period: {} Contradicted: {}", r.hypothesis.name(), r.period, c),
+                                })
+                                .collect::<Vec<String>>()
+                                .join("\n")))
+                        }));
+                    }
+                    ui.add_space(16.0);
+                    ui.label(egui::RichText::new("Corpus Directory").size(24.0).color(egui::Color32::LIGHT_GREEN));
+                    ui.add_sized(
+                        [100.0, 32.0],
+                        egui::TextEdit::singleline(&mut self.corpus_dir)
+                            .font(FontId::monospace(20.0))
+                            .hint_text("Folder of key texts").text_color(egui::Color32::LIGHT_YELLOW),
+                    );
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Running Key Search\nEncrypted, Plaintext, Key 1, Corpus -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let (encrypted, plaintext, corpus_dir, keyword, alphabet) = (self.encrypted.clone(), self.plaintext.clone(), self.corpus_dir.clone(), keyword.clone(), alphabet.clone());
+                        self.jobs.push(Job::spawn("Running Key Search", move |monitor| {
+                            let corpus = load_corpus(Path::new(&corpus_dir)).map_err(|e| KryptonError::Io(format!("Could not read corpus: {}", e)))?;
+                            Ok(JobResult::Output(running_key_search_monitored(&alphabet?.keyed(&keyword), &encrypted, &Family::ALL, &corpus, &plaintext, 10, monitor)
+                                .iter()
+                                .map(|c| format!("{} @ {} {} Crib: {} Fitness: {:.3}\nKey: {}\n{}", c.source, c.offset, c.family.name(), c.crib_matches, c.fitness, c.key, c.plaintext))
+                                .collect::<Vec<String>>()
+                                .join("\n")))
+                        }));
+                    }
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Columnar Transposition Search\nEncrypted, Max Key Length -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let (encrypted, key_length) = (self.encrypted.clone(), self.key_length);
+                        self.jobs.push(Job::spawn("Columnar Transposition Search", move |monitor| {
+                            let candidates = columnar_solve_monitored(&encrypted, key_length, 20, 1, monitor);
+                            let text = candidates
+                                .iter()
+                                .take(10)
+                                .map(|c| format!("Width: {} Key: {} Fitness: {:.3}\n{}", c.width, c.key, c.fitness, c.plaintext))
+                                .collect::<Vec<String>>()
+                                .join("\n");
+                            let keys = candidates.first().map(|best| vec![best.key.clone()]);
+                            Ok(JobResult::Solution { text, cipher: "Columnar Transposition", keys })
+                        }));
+                    }
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Rotating Grid Search\nEncrypted, Plaintext, Max Key Length -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let (encrypted, plaintext, key_length) = (self.encrypted.clone(), self.plaintext.clone(), self.key_length);
+                        self.jobs.push(Job::spawn("Rotating Grid Search", move |monitor| {
+                            Ok(JobResult::Output(rotating_grid_search_monitored(&encrypted, &plaintext, key_length, 10, monitor)
+                                .iter()
+                                .map(|c| format!("Widths: {} {} {} Crib: {} Fitness: {:.3}\n{}", c.width1, c.width2, c.rotation.name(), c.crib_matches, c.fitness, c.plaintext))
+                                .collect::<Vec<String>>()
+                                .join("\n")))
+                        }));
+                    }
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Kullback Test\nEncrypted, Max Key Length -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        let (encrypted, key_length, alphabet) = (self.encrypted.clone(), self.key_length, alphabet.clone());
+                        self.jobs.push(Job::spawn("Kullback Test", move |monitor| {
+                            Ok(JobResult::Output(kullback_graph(&try_kullback_monitored(&encrypted, &alphabet?, key_length, monitor)?)))
+                        }));
+                    }
+                    ui.add_space(16.0);
+                    ui.label(egui::RichText::new("Annealing").size(24.0).color(egui::Color32::LIGHT_GREEN));
+                    ui.add(egui::Slider::new(&mut self.anneal.keyword1_length, 1..=26).prefix("Key 1 Length:  ").text_color(egui::Color32::LIGHT_YELLOW));
+                    ui.add(egui::Slider::new(&mut self.anneal.start_temperature, 0.1..=20.0).prefix("Start Temperature:  ").text_color(egui::Color32::LIGHT_YELLOW));
+                    ui.add(egui::Slider::new(&mut self.anneal.end_temperature, 0.01..=5.0).prefix("End Temperature:  ").text_color(egui::Color32::LIGHT_YELLOW));
+                    ui.add(egui::Slider::new(&mut self.anneal.steps, 1000..=1000000).logarithmic(true).prefix("Steps:  ").text_color(egui::Color32::LIGHT_YELLOW));
+                    ui.add(egui::Slider::new(&mut self.anneal.restarts, 1..=100).prefix("Restarts:  ").text_color(egui::Color32::LIGHT_YELLOW));
+                    ui.add(egui::DragValue::new(&mut self.anneal.seed).prefix("Seed:  "));
+                    if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Anneal Keyed Vigenere\nEncrypted, Max Key Length -> Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                        self.anneal.keyword2_length = self.key_length;
+                        let (encrypted, config, alphabet) = (self.encrypted.clone(), self.anneal.clone(), alphabet.clone());
+                        self.jobs.push(Job::spawn("Anneal Keyed Vigenere", move |monitor| {
+                            let pairs = anneal_keyed_vigenere_monitored(&alphabet?, &encrypted, &config, monitor);
+                            let text = pairs
+                                .iter()
+                                .map(|p| format!("Key 1: {} Key 2: {} Score: {:.1}\n{}", p.keyword1, p.keyword2, p.score, p.plaintext))
+                                .collect::<Vec<String>>()
+                                .join("\n");
+                            let keys = pairs.first().map(|best| vec![best.keyword1.clone(), best.keyword2.clone()]);
+                            Ok(JobResult::Solution { text, cipher: "Keyed Vigenere", keys })
+                        }));
+                    }
                     ui.add_space(16.0);     
                 });
         });
@@ -176,19 +647,27 @@ impl eframe::App for MyApp {
                 ui.add_space(32.0);
             });
 
-            let k1_options = vec![
-                ("Kryptos Section 1 Encrypted".to_string(), K1.to_string()),
-                ("Kryptos Section 2 Encrypted".to_string(), K2.to_string()),
-                ("Kryptos Section 3 Encrypted".to_string(), K3.to_string()),
-                ("Kryptos Section 4 Encrypted".to_string(), K4.to_string()),
-            ];
-
-            let k1p_options = vec![
-                ("Kryptos Section 1 Plaintext".to_string(), K1p.to_string()),
-                ("Kryptos Section 2 Plaintext".to_string(), K2p.to_string()),
-                ("Kryptos Section 3 Plaintext".to_string(), K3p.to_string()),
-                ("Kryptos Section 4 Plaintext".to_string(), K4p.to_string()),
-            ];
+            ui.horizontal(|ui| {
+                if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Open Session\nSession File -> Everything").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                    if let Some(path) = rfd::FileDialog::new().add_filter("KRYPTON Session", &["json"]).pick_file() {
+                        self.open_session(&path);
+                    }
+                }
+                if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Save Session\nEverything -> Session File").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                    let path = self.session_path.clone().or_else(|| rfd::FileDialog::new().add_filter("KRYPTON Session", &["json"]).set_file_name("session.json").save_file());
+                    if let Some(path) = path {
+                        self.save_session(&path);
+                    }
+                }
+                if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Save Session As\nEverything -> New File").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                    if let Some(path) = rfd::FileDialog::new().add_filter("KRYPTON Session", &["json"]).set_file_name("session.json").save_file() {
+                        self.save_session(&path);
+                    }
+                }
+                let file = self.session_path.as_ref().map_or("Unsaved Session".to_string(), |path| path.display().to_string());
+                ui.label(egui::RichText::new(file).size(16.0).color(egui::Color32::WHITE));
+            });
+            ui.add_space(16.0);
 
             ui.horizontal(|ui| {
                 ui.add_sized(
@@ -205,13 +684,38 @@ impl eframe::App for MyApp {
                         .hint_text("Known Plain Text"),
                 );
             });
-            if ui.add_sized([500.0, 50.0],(egui::Button::new(egui::RichText::new("Analyze Encrypted & Plaintext").size(24.0).color(egui::Color32::LIGHT_GREEN)))).clicked() {
-                self.analysis = Some(analyze(&self.encrypted, &self.plaintext, self.key_length));
+            if ui.add_sized([500.0, 50.0],egui::Button::new(egui::RichText::new("Analyze Encrypted & Plaintext").size(24.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                let excluded: Vec<usize> = self.excluded_factors.split(',').filter_map(|f| f.trim().parse().ok()).collect();
+                let alphabet = Alphabet::choice(self.alphabet, &self.custom_alphabet);
+                let (encrypted, plaintext, key_length, significance) = (self.encrypted.clone(), self.plaintext.clone(), self.key_length, self.significance.clone());
+                self.jobs.push(Job::spawn("Analyze Encrypted & Plaintext", move |monitor| {
+                    Ok(JobResult::Analysis(Box::new(try_analyze_monitored(&encrypted, &plaintext, key_length, &excluded, &alphabet?, &significance, monitor)?)))
+                }));
             } 
             ui.horizontal(|ui| {
-                
-                ui.add_space(1.0);
-                
+                ui.label(egui::RichText::new("Kasiski Excluded Factors").size(16.0).color(egui::Color32::LIGHT_GREEN));
+                ui.add_sized(
+                    [200.0, 24.0],
+                    egui::TextEdit::singleline(&mut self.excluded_factors)
+                        .font(FontId::monospace(16.0))
+                        .hint_text("Comma separated"),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("Significance Trials").size(16.0).color(egui::Color32::LIGHT_GREEN));
+                ui.add(egui::DragValue::new(&mut self.significance.trials).clamp_range(0..=10000));
+                ui.label(egui::RichText::new("Seed").size(16.0).color(egui::Color32::LIGHT_GREEN));
+                ui.add(egui::DragValue::new(&mut self.significance.seed));
+                ui.checkbox(&mut self.significance.english, egui::RichText::new("English Baseline").size(16.0).color(egui::Color32::LIGHT_GREEN));
+            });
+            ui.horizontal(|ui| {
+                let analysis = || self.analysis.as_ref().ok_or_else(|| KryptonError::Invalid { input: "Analysis", reason: "analyze a text first".to_string() });
+                if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Export Analysis\nAnalysis -> JSON Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                    show_result(analysis().and_then(to_json), &mut self.output, &mut self.error);
+                }
+                if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Export Analysis\nAnalysis -> CSV Output").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                    show_result(analysis().and_then(|value| to_csv(std::slice::from_ref(value))), &mut self.output, &mut self.error);
+                }
             });
 
             ui.add_space(16.0); 
@@ -221,22 +725,42 @@ impl eframe::App for MyApp {
             ui.add_space(16.0);
 
            
-            egui::ComboBox::from_label("Select Encrypted Text")
-                .selected_text(k1_options.iter().find(|&(_, v)| *v == self.encrypted).unwrap_or_else(|| &k1_options[0]).0.clone())
-                .show_ui(ui, |ui| {
-                    for (display, value) in &k1_options {
-                        ui.selectable_value(&mut self.encrypted, value.clone(), egui::RichText::new(display).size(16.0));
-                    }
-                });
-            ui.add_space(16.0);
-            egui::ComboBox::from_label("Select Known Plaintext")
-                .selected_text(k1p_options.iter().find(|&(_, v)| *v == self.plaintext).unwrap_or_else(|| &k1p_options[0]).0.clone())
-                .show_ui(ui, |ui| {
-                    for (display, value) in &k1p_options {
-                        ui.selectable_value(&mut self.plaintext, value.clone(), egui::RichText::new(display).size(16.0));
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("Test Cases").size(16.0).color(egui::Color32::LIGHT_GREEN));
+                ui.add_sized(
+                    [300.0, 24.0],
+                    egui::TextEdit::singleline(&mut self.cases_dir)
+                        .font(FontId::monospace(16.0))
+                        .hint_text("Cases Directory"),
+                );
+                if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Reload Cases\nCases Directory -> Browser").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                    self.reload_cases();
+                }
+                egui::ComboBox::from_label("Filter by Cipher Type")
+                    .selected_text(egui::RichText::new(self.case_filter.as_deref().unwrap_or("All")).size(16.0))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.case_filter, None, egui::RichText::new("All").size(16.0));
+                        for cipher in cipher_types(&self.cases) {
+                            ui.selectable_value(&mut self.case_filter, Some(cipher.clone()), egui::RichText::new(cipher).size(16.0));
+                        }
+                    });
+            });
+            let mut selected = None;
+            egui::ScrollArea::vertical().id_source("cases").max_height(160.0).show(ui, |ui| {
+                let shown = self.cases.iter().enumerate().filter(|(_, case)| self.case_filter.as_ref().is_none_or(|cipher| *cipher == case.cipher));
+                for (index, case) in shown {
+                    let label = format!("{:<28}{:<24}{}", case.name, case.cipher, case.ciphertext.chars().count());
+                    if ui.selectable_label(self.case == Some(index), egui::RichText::new(label).font(FontId::monospace(16.0))).clicked() {
+                        selected = Some(index);
                     }
-                });
-            
+                }
+            });
+            if let Some(index) = selected {
+                self.load_case(index);
+            }
+            if let Some(case) = self.case.and_then(|index| self.cases.get(index)) {
+                ui.label(egui::RichText::new(&case.notes).size(16.0).color(egui::Color32::WHITE));
+            }
             ui.add_space(16.0);
             ui.horizontal(|ui| {
                 ui.add_sized(
@@ -254,6 +778,91 @@ impl eframe::App for MyApp {
             ui.add_space(16.0);
             ui.label(egui::RichText::new(&self.terminal4).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
             });
+            if let Some(error) = &self.error {
+                ui.label(egui::RichText::new(error.to_string()).color(egui::Color32::LIGHT_RED).font(FontId::monospace(16.0)));
+            }
+            for job in &self.jobs {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(&job.name).size(16.0).color(egui::Color32::LIGHT_GREEN));
+                    let bar = match job.progress() {
+                        Some(progress) => egui::ProgressBar::new(progress as f32).show_percentage(),
+                        None => egui::ProgressBar::new(0.0).animate(true),
+                    };
+                    ui.add_sized([300.0, 20.0], bar);
+                    ui.label(egui::RichText::new(format!("{:.1}s", job.elapsed().as_secs_f64())).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                    if job.is_cancelled() {
+                        ui.label(egui::RichText::new("Cancelling").color(egui::Color32::YELLOW).font(FontId::monospace(16.0)));
+                    } else if ui.button(egui::RichText::new("Cancel").size(16.0).color(egui::Color32::LIGHT_RED)).clicked() {
+                        job.cancel();
+                    }
+                });
+                if let Some(best) = job.best() {
+                    ui.label(egui::RichText::new(format!("Best: {}", best)).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                }
+            }
+
+            ui.add_space(16.0);
+            ui.label(egui::RichText::new("Output vs Known Plaintext").size(16.0).color(egui::Color32::LIGHT_GREEN));
+            let key_length = self.key_length.max(1);
+            let period_key = self.ciphers[self.cipher].period_slot().and_then(|slot| self.keys.get(slot)).map_or("", |k| k.as_str());
+            show_comparison(ui, &compare(&self.output, &self.plaintext, key_length), key_length, period_key);
+
+            ui.add_space(16.0);
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new("Crib").size(16.0).color(egui::Color32::LIGHT_GREEN));
+                ui.add_sized(
+                    [300.0, 24.0],
+                    egui::TextEdit::singleline(&mut self.crib)
+                        .font(FontId::monospace(16.0))
+                        .hint_text("Crib to slide along the ciphertext"),
+                );
+                if ui.add_sized([300.0,20.0],egui::Button::new(egui::RichText::new("Drag Crib\nEncrypted, Crib, Key 1 -> Table").size(16.0).color(egui::Color32::LIGHT_GREEN))).clicked() {
+                    let alphabet = Alphabet::choice(self.alphabet, &self.custom_alphabet);
+                    let keyword = self.keys.first().cloned().unwrap_or_default();
+                    let (encrypted, crib) = (self.encrypted.clone(), self.crib.clone());
+                    self.jobs.push(Job::spawn("Drag Crib", move |monitor| {
+                        Ok(JobResult::CribMatches(crib_drag_monitored(&encrypted, &crib, &alphabet?, &keyword, monitor)))
+                    }));
+                }
+            });
+            if !self.crib_matches.is_empty() {
+                egui::ScrollArea::vertical().id_source("CRIB").max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("CRIB MATCHES").striped(true).show(ui, |ui| {
+                        for column in CribColumn::ALL {
+                            let arrow = match (column == self.crib_sort, self.crib_descending) {
+                                (true, true) => " v",
+                                (true, false) => " ^",
+                                (false, _) => "",
+                            };
+                            let heading = egui::RichText::new(format!("{}{}", column.name(), arrow)).color(egui::Color32::LIGHT_GREEN).font(FontId::monospace(16.0));
+                            if ui.add(egui::Label::new(heading).sense(egui::Sense::click())).clicked() {
+                                self.crib_descending = if column == self.crib_sort { !self.crib_descending } else { true };
+                                self.crib_sort = column;
+                                sort_matches(&mut self.crib_matches, self.crib_sort, self.crib_descending);
+                            }
+                        }
+                        ui.end_row();
+                        for m in self.crib_matches.iter().take(500) {
+                            let tableau = if m.keyword.is_empty() { "STRAIGHT" } else { m.keyword.as_str() };
+                            let cells = [
+                                m.offset.to_string(),
+                                m.family.name().to_string(),
+                                tableau.to_string(),
+                                m.fragment.clone(),
+                                m.period.to_string(),
+                                format!("{:.2}", m.periodicity),
+                                m.repeat.clone(),
+                                format!("{:.2}", m.fitness),
+                                format!("{:.2}", m.score),
+                            ];
+                            for cell in cells {
+                                ui.label(egui::RichText::new(cell).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
             
         });
         
@@ -261,6 +870,69 @@ impl eframe::App for MyApp {
     }
 }
 
+/// Puts a successful result in the output box and clears the error, or keeps the output
+/// and shows the error.
+fn show_result(result: Result<String, KryptonError>, output: &mut String, error: &mut Option<KryptonError>) {
+    match result {
+        Ok(text) => {
+            *output = text;
+            *error = None;
+        }
+        Err(e) => *error = Some(e),
+    }
+}
+
+/// The output over the plaintext in rows a whole number of keys wide, so each key position
+/// keeps its column: matches green, mismatches red, `_` grey and overhang yellow. Below,
+/// the mismatches of each key position and the positions whose key symbol looks wrong.
+fn show_comparison(ui: &mut egui::Ui, comparison: &Comparison, key_length: usize, key: &str) {
+    let color = |agreement: Agreement| match agreement {
+        Agreement::Match => egui::Color32::LIGHT_GREEN,
+        Agreement::Mismatch => egui::Color32::LIGHT_RED,
+        Agreement::Unknown => egui::Color32::GRAY,
+        Agreement::Missing => egui::Color32::YELLOW,
+    };
+    let width = key_length * (60 / key_length).max(1);
+    let mut job = egui::text::LayoutJob::default();
+    for row in comparison.chars.chunks(width) {
+        for line in [0, 1] {
+            for diff in row {
+                let c = if line == 0 { diff.output } else { diff.plaintext };
+                let format = egui::TextFormat::simple(FontId::monospace(16.0), color(diff.agreement));
+                job.append(&c.unwrap_or(' ').to_string(), 0.0, format);
+            }
+            job.append("\n", 0.0, egui::TextFormat::simple(FontId::monospace(16.0), egui::Color32::WHITE));
+        }
+        job.append("\n", 0.0, egui::TextFormat::simple(FontId::monospace(16.0), egui::Color32::WHITE));
+    }
+    ui.label(job);
+
+    let summary = format!(
+        "MATCHES {}   MISMATCHES {}   UNKNOWN {}   MISSING {}",
+        comparison.matches, comparison.mismatches, comparison.unknown, comparison.missing
+    );
+    ui.label(egui::RichText::new(summary).color(egui::Color32::WHITE).font(FontId::monospace(16.0)));
+    if comparison.mismatches == 0 {
+        return;
+    }
+    let key: Vec<char> = key.chars().collect();
+    for column in &comparison.columns {
+        let letter = key.get(column.position).map_or(String::new(), |c| format!(" ({})", c));
+        let line = format!(
+            "KEY POSITION {:<3}{:<5}| {} | {}/{}",
+            column.position, letter, percentage_blocks(column.rate(), 0.0, 1.0), column.mismatches, column.compared
+        );
+        let color = if comparison.suspects.contains(&column.position) { egui::Color32::LIGHT_RED } else { egui::Color32::WHITE };
+        ui.label(egui::RichText::new(line).color(color).font(FontId::monospace(16.0)));
+    }
+    let verdict = if comparison.suspects.is_empty() {
+        "No key position stands out: the mismatches are spread, as from misspellings or a wrong cipher".to_string()
+    } else {
+        format!("Likely wrong key position: {}", comparison.suspects.iter().join(", "))
+    };
+    ui.label(egui::RichText::new(verdict).color(egui::Color32::LIGHT_RED).font(FontId::monospace(16.0)));
+}
+
 fn main() -> Result<(), eframe::Error> {
 
     let options = eframe::NativeOptions {
@@ -274,7 +946,14 @@ fn main() -> Result<(), eframe::Error> {
             // This gives us image support:
             egui_extras::install_image_loaders(&cc.egui_ctx);
 
-            Box::<MyApp>::default()
+            let mut app = MyApp::default();
+            if let Some(storage) = cc.storage {
+                if let Some(session) = eframe::get_value::<Session>(storage, eframe::APP_KEY) {
+                    app.restore(session);
+                }
+                app.session_path = eframe::get_value(storage, SESSION_PATH_KEY).flatten();
+            }
+            Box::new(app)
         }),
     )
 }
\ No newline at end of file
@@ -0,0 +1,215 @@
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+
+use crate::error::{check_range, KryptonError, Result};
+
+// Counted from the prose of The Rust Programming Language; data/README.md has the details.
+const ENGLISH_BIGRAMS: &str = include_str!("../data/english_bigrams.txt");
+const ENGLISH_TRIGRAMS: &str = include_str!("../data/english_trigrams.txt");
+const ENGLISH_QUADGRAMS: &str = include_str!("../data/english_quadgrams.txt");
+const ENGLISH_QUINTGRAMS: &str = include_str!("../data/english_quintgrams.txt");
+
+/// Log10 probabilities of letter n-grams, for n from 2 to 5.
+///
+/// Up to quadgrams every n-gram has a slot in a dense table. The 26^5 quintgrams are too many
+/// for that, so only the ones in the table are stored.
+///
+/// Tables are read from plain text with one `NGRAM COUNT` pair per line, e.g. `TION 14077`.
+/// N-grams missing from the table score a floor of a hundredth of a single occurrence.
+pub struct NgramModel {
+    pub n: usize,
+    dense: Vec<f64>,
+    sparse: HashMap<usize, f64>,
+    floor: f64,
+}
+
+impl NgramModel {
+    pub fn from_counts(n: usize, counts: &[(String, u64)]) -> Result<NgramModel> {
+        check_range("N-gram length", n, 2, 5)?;
+        let total: u64 = counts.iter().map(|(_, count)| count).sum();
+        if total == 0 {
+            return Err(KryptonError::EmptyText("N-gram table"));
+        }
+        let total = total as f64;
+        let floor = (0.01 / total).log10();
+
+        let mut model = NgramModel { n, dense: Vec::new(), sparse: HashMap::new(), floor };
+        if n <= 4 {
+            model.dense = vec![floor; 26usize.pow(n as u32)];
+        }
+        for (gram, count) in counts {
+            let index = gram_index(gram.as_bytes()).filter(|_| gram.len() == n);
+            let Some(index) = index else {
+                return Err(invalid(format!("'{}' is not a {}-letter n-gram", gram, n)));
+            };
+            let score = (*count as f64 / total).log10();
+            if n <= 4 {
+                model.dense[index] = score;
+            } else {
+                model.sparse.insert(index, score);
+            }
+        }
+        Ok(model)
+    }
+
+    /// Parses an `NGRAM COUNT` table. The n-gram length is taken from the first entry.
+    pub fn parse(table: &str) -> Result<NgramModel> {
+        let mut counts = Vec::new();
+        for (line_number, line) in table.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let (Some(gram), Some(count)) = (parts.next(), parts.next()) else {
+                return Err(invalid(format!("line {}: expected NGRAM COUNT", line_number + 1)));
+            };
+            let count = count
+                .parse::<u64>()
+                .map_err(|_| invalid(format!("line {}: '{}' is not a count", line_number + 1, count)))?;
+            counts.push((gram.to_uppercase(), count));
+        }
+        let n = counts.first().map(|(gram, _)| gram.len()).unwrap_or(0);
+        NgramModel::from_counts(n, &counts)
+    }
+
+    pub fn load(path: &Path) -> Result<NgramModel> {
+        let table = fs::read_to_string(path).map_err(|e| KryptonError::Io(format!("{}: {}", path.display(), e)))?;
+        NgramModel::parse(&table)
+    }
+
+    /// Built-in English model for n from 2 to 5.
+    pub fn english(n: usize) -> Option<&'static NgramModel> {
+        static MODELS: [OnceLock<NgramModel>; 4] = [OnceLock::new(), OnceLock::new(), OnceLock::new(), OnceLock::new()];
+        let table = match n {
+            2 => ENGLISH_BIGRAMS,
+            3 => ENGLISH_TRIGRAMS,
+            4 => ENGLISH_QUADGRAMS,
+            5 => ENGLISH_QUINTGRAMS,
+            _ => return None,
+        };
+        Some(MODELS[n - 2].get_or_init(|| NgramModel::parse(table).expect("built-in n-gram table")))
+    }
+
+    /// Log10 probability of one n-gram given as letter indices 0..26.
+    pub fn gram_score(&self, gram: &[usize]) -> f64 {
+        let index = gram.iter().fold(0, |acc, &c| acc * 26 + c);
+        if self.n <= 4 {
+            self.dense[index]
+        } else {
+            self.sparse.get(&index).copied().unwrap_or(self.floor)
+        }
+    }
+
+    /// Sum of log10 probabilities over a text given as letter indices 0..26.
+    pub fn score_indices(&self, text: &[usize]) -> f64 {
+        text.windows(self.n).map(|gram| self.gram_score(gram)).sum()
+    }
+
+    /// Sum of log10 probabilities of every n-gram in the letters of `text`.
+    pub fn score(&self, text: &str) -> f64 {
+        self.score_indices(&letter_indices(text))
+    }
+
+    /// Average log10 probability per n-gram, comparable across texts of different lengths.
+    /// Texts shorter than one n-gram score the floor.
+    pub fn fitness(&self, text: &str) -> f64 {
+        let letters = letter_indices(text);
+        if letters.len() < self.n {
+            return self.floor;
+        }
+        self.score_indices(&letters) / (letters.len() - self.n + 1) as f64
+    }
+}
+
+fn invalid(reason: String) -> KryptonError {
+    KryptonError::Invalid { input: "N-gram table", reason }
+}
+
+fn gram_index(gram: &[u8]) -> Option<usize> {
+    gram.iter().try_fold(0, |acc, &b| {
+        b.is_ascii_alphabetic().then(|| acc * 26 + (b.to_ascii_uppercase() - b'A') as usize)
+    })
+}
+
+fn letter_indices(text: &str) -> Vec<usize> {
+    text.bytes()
+        .filter(|b| b.is_ascii_alphabetic())
+        .map(|b| (b.to_ascii_uppercase() - b'A') as usize)
+        .collect()
+}
+
+/// Every overlapping n-gram of the letters of `text`, across word boundaries, most frequent
+/// first and alphabetical among equals. This is how the built-in tables were counted.
+pub fn count_ngrams(text: &str, n: usize) -> Vec<(String, u64)> {
+    let letters: Vec<u8> = text.bytes().filter(u8::is_ascii_alphabetic).map(|b| b.to_ascii_uppercase()).collect();
+    let mut counts: HashMap<&[u8], u64> = HashMap::new();
+    for gram in letters.windows(n.max(1)) {
+        *counts.entry(gram).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(String, u64)> = counts.into_iter().map(|(gram, count)| (String::from_utf8_lossy(gram).to_string(), count)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// An `NGRAM COUNT` table, as read by `NgramModel::parse`, of the n-grams seen at least
+/// `min_count` times.
+pub fn ngram_table(counts: &[(String, u64)], min_count: u64) -> String {
+    counts.iter().filter(|(_, count)| *count >= min_count).map(|(gram, count)| format!("{} {}\n", gram, count)).collect()
+}
+
+/// Quadgram fitness of `text` under the built-in English model; higher is more English.
+pub fn english_fitness(text: &str) -> f64 {
+    NgramModel::english(4).map(|model| model.fitness(text)).unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypt::{K1p, K2p, K3p};
+
+    #[test]
+    fn tables_read_back_as_counted() {
+        let text = [K1p, K2p, K3p].concat();
+        for n in 2..=5 {
+            let counts = count_ngrams(&text, n);
+            let counted = NgramModel::from_counts(n, &counts).unwrap();
+            let path = std::env::temp_dir().join(format!("krypton-ngrams-{}-{}.txt", n, std::process::id()));
+            fs::write(&path, format!("# comment\n{}", ngram_table(&counts, 1))).unwrap();
+            let loaded = NgramModel::load(&path);
+            fs::remove_file(&path).unwrap();
+            let loaded = loaded.unwrap();
+            assert_eq!(loaded.n, n);
+            assert_eq!(loaded.score(&text), counted.score(&text));
+        }
+    }
+
+    #[test]
+    fn unseen_ngrams_score_the_floor() {
+        let model = NgramModel::parse("THE 3\nAND 1").unwrap();
+        let floor = (0.01f64 / 4.0).log10();
+        assert_eq!(model.gram_score(&[25, 25, 25]), floor);
+        assert_eq!(model.fitness("QQ"), floor);
+        assert!(model.gram_score(&[19, 7, 4]) > model.gram_score(&[0, 13, 3]));
+
+        let model = NgramModel::parse("THERE 2").unwrap();
+        assert_eq!(model.score("QQQQQ"), (0.01f64 / 2.0).log10());
+    }
+
+    #[test]
+    fn english_scores_above_random_text() {
+        let random: String = (0..K3p.len()).map(|i| (b'A' + (i * 7 + i * i * 3) as u8 % 26) as char).collect();
+        for n in 2..=5 {
+            let model = NgramModel::english(n).unwrap();
+            assert!(model.fitness(K3p) > model.fitness(&random) + 0.5, "{}", n);
+        }
+    }
+
+    #[test]
+    fn lengths_outside_two_to_five_are_rejected() {
+        assert!(NgramModel::from_counts(1, &[("E".to_string(), 1)]).is_err());
+        assert!(NgramModel::from_counts(6, &[("THEREI".to_string(), 1)]).is_err());
+        assert!(NgramModel::parse("THEREIS 1").is_err());
+        assert!(NgramModel::parse("TH 1\nTHE 1").is_err());
+        assert!(NgramModel::english(1).is_none() && NgramModel::english(6).is_none());
+    }
+}
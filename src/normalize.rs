@@ -0,0 +1,117 @@
+use crate::{alphabet::Alphabet, error::Result};
+
+/// Text stripped to the symbols of an alphabet, remembering where each one came from so a
+/// processed version can be put back into the original layout. Anything outside the
+/// alphabet, such as spaces, punctuation and the `?` of the Kryptos transcriptions, stays
+/// where it was.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Normalized {
+    /// The alphabet symbols in order, merged and in the alphabet's case.
+    pub text: String,
+    /// Character position in the original text of every symbol of `text`.
+    pub positions: Vec<usize>,
+    original: Vec<char>,
+}
+
+impl Normalized {
+    pub fn new(text: &str, alphabet: &Alphabet) -> Normalized {
+        let original: Vec<char> = text.chars().collect();
+        let (positions, symbols): (Vec<usize>, String) = original
+            .iter()
+            .enumerate()
+            .filter_map(|(position, &c)| alphabet.index(c).map(|i| (position, alphabet.symbol(i))))
+            .unzip();
+        Normalized { text: symbols, positions, original }
+    }
+
+    /// Position in the original text of the `index`-th symbol.
+    pub fn position(&self, index: usize) -> Option<usize> {
+        self.positions.get(index).copied()
+    }
+
+    /// Writes the symbols of `processed` over the original symbols in order, keeping the
+    /// original's case, spacing and punctuation. Symbols left over when `processed` is longer
+    /// go on the end; original symbols with nothing to replace them are dropped.
+    pub fn restore(&self, processed: &str) -> String {
+        let mut symbols = processed.chars();
+        let mut restored = String::with_capacity(self.original.len());
+        let mut kept = self.positions.iter().peekable();
+        for (position, &c) in self.original.iter().enumerate() {
+            if kept.peek() != Some(&&position) {
+                restored.push(c);
+                continue;
+            }
+            kept.next();
+            if let Some(symbol) = symbols.next() {
+                if c.is_lowercase() {
+                    restored.extend(symbol.to_lowercase());
+                } else {
+                    restored.push(symbol);
+                }
+            }
+        }
+        restored.extend(symbols);
+        restored
+    }
+}
+
+/// Runs `process` on the alphabet symbols of `text` and restores the original format around
+/// its output.
+pub fn preserve_format(text: &str, alphabet: &Alphabet, process: impl FnOnce(&str) -> String) -> String {
+    let normalized = Normalized::new(text, alphabet);
+    normalized.restore(&process(&normalized.text))
+}
+
+/// `preserve_format` for processing that can fail.
+pub fn try_preserve_format(text: &str, alphabet: &Alphabet, process: impl FnOnce(&str) -> Result<String>) -> Result<String> {
+    let normalized = Normalized::new(text, alphabet);
+    Ok(normalized.restore(&process(&normalized.text)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::KryptonError,
+        toolkit::{try_periodic_decrypt_in, try_periodic_encrypt_in, Family},
+    };
+
+    const TEXT: &str = "Between subtle shading and the absence of light\nlies the nuance of Iqlusion.";
+
+    #[test]
+    fn round_trip_keeps_case_spacing_and_punctuation() {
+        let alphabet = Alphabet::standard();
+        let encrypted = try_preserve_format(TEXT, &alphabet, |text| try_periodic_encrypt_in(&alphabet, text, Family::Vigenere, "KRYPTOS")).unwrap();
+        let layout = |text: &str| -> String {
+            text.chars()
+                .map(|c| match c {
+                    c if c.is_uppercase() => 'A',
+                    c if c.is_lowercase() => 'a',
+                    c => c,
+                })
+                .collect()
+        };
+        assert_eq!(layout(&encrypted), layout(TEXT));
+        assert_ne!(encrypted, TEXT);
+        let decrypted = try_preserve_format(&encrypted, &alphabet, |text| try_periodic_decrypt_in(&alphabet, text, Family::Vigenere, "KRYPTOS")).unwrap();
+        assert_eq!(decrypted, TEXT);
+    }
+
+    #[test]
+    fn characters_outside_the_alphabet_stay_put() {
+        let alphabet = Alphabet::merged_ij();
+        let text = "Jo? 42 señal!";
+        let normalized = Normalized::new(text, &alphabet);
+        assert_eq!(normalized.text, "IOSEAL");
+        assert_eq!(normalized.positions, [0, 1, 7, 8, 10, 11]);
+        let restored = try_preserve_format(text, &alphabet, |text| Ok(text.chars().rev().collect())).unwrap();
+        assert_eq!(restored, "La? 42 esñoi!");
+    }
+
+    #[test]
+    fn errors_from_processing_are_passed_on() {
+        let alphabet = Alphabet::standard();
+        let result = try_preserve_format(TEXT, &alphabet, |text| try_periodic_encrypt_in(&alphabet, text, Family::Vigenere, ""));
+        assert!(matches!(result, Err(KryptonError::EmptyKey(_))));
+    }
+}
@@ -0,0 +1,185 @@
+use std::{fs, io, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    alphabet::Alphabet,
+    jobs::{Monitor, Unmonitored},
+    ngram::english_fitness,
+    toolkit::Family,
+};
+
+#[derive(Serialize)]
+pub struct RunningKeyCandidate {
+    pub source: String,
+    pub offset: usize,
+    pub family: Family,
+    pub key: String,
+    pub plaintext: String,
+    pub crib_matches: usize,
+    pub fitness: f64,
+}
+
+/// Reads every file in `dir` as a candidate key text, in upper case without whitespace.
+/// Whatever else is outside the alphabet of a search is skipped by the search.
+pub fn load_corpus(dir: &Path) -> io::Result<Vec<(String, String)>> {
+    let mut corpus = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(&path) else { continue };
+        let letters: String = text.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        corpus.push((name, letters));
+    }
+    corpus.sort();
+    Ok(corpus)
+}
+
+/// Slides every key text across the ciphertext and keeps the `limit` best offsets.
+/// Offsets are ranked by how many letters of the crib (aligned with the ciphertext,
+/// `_` for unknown, like `K4p`) they reproduce, then by quadgram fitness.
+pub fn running_key_search(
+    ciphertext: &str,
+    key1: &str,
+    families: &[Family],
+    corpus: &[(String, String)],
+    crib: &str,
+    limit: usize,
+) -> Vec<RunningKeyCandidate> {
+    running_key_search_monitored(&Alphabet::standard().keyed(key1), ciphertext, families, corpus, crib, limit, &Unmonitored)
+}
+
+/// `running_key_search` over an already keyed `alphabet`, reporting each key text as it is
+/// started and every new best offset as it is found, and returning what it has when cancelled. Offsets count
+/// the key text's symbols in the alphabet.
+pub fn running_key_search_monitored(
+    alphabet: &Alphabet,
+    ciphertext: &str,
+    families: &[Family],
+    corpus: &[(String, String)],
+    crib: &str,
+    limit: usize,
+    monitor: &dyn Monitor,
+) -> Vec<RunningKeyCandidate> {
+    let cipher = alphabet.indices(ciphertext);
+    let size = alphabet.len();
+    let known: Vec<(usize, usize)> = crib
+        .chars()
+        .take(cipher.len())
+        .enumerate()
+        .filter_map(|(i, c)| alphabet.index(c).map(|p| (i, p)))
+        .collect();
+    let mut best: Vec<RunningKeyCandidate> = Vec::with_capacity(limit + 1);
+    if cipher.is_empty() || limit == 0 {
+        return best;
+    }
+
+    for (done, (source, text)) in corpus.iter().enumerate() {
+        if monitor.cancelled() {
+            break;
+        }
+        monitor.progress(done, corpus.len());
+        let key = alphabet.indices(text);
+        if key.len() < cipher.len() {
+            continue;
+        }
+        for offset in 0..=key.len() - cipher.len() {
+            let window = &key[offset..offset + cipher.len()];
+            for &family in families {
+                let crib_matches = known
+                    .iter()
+                    .filter(|&&(i, p)| family.decrypt_index_in(cipher[i], window[i], size) == p)
+                    .count();
+                if best.len() == limit && best.last().is_some_and(|worst| crib_matches < worst.crib_matches) {
+                    continue;
+                }
+
+                let plaintext: String = cipher
+                    .iter()
+                    .zip(window)
+                    .map(|(&c, &k)| alphabet.symbol(family.decrypt_index_in(c, k, size)))
+                    .collect();
+                let fitness = english_fitness(&plaintext);
+                let position = best
+                    .iter()
+                    .position(|b| crib_matches > b.crib_matches || (crib_matches == b.crib_matches && fitness > b.fitness))
+                    .unwrap_or(best.len());
+                if position < limit {
+                    best.insert(position, RunningKeyCandidate {
+                        source: source.clone(),
+                        offset,
+                        family,
+                        key: alphabet.text(window),
+                        plaintext,
+                        crib_matches,
+                        fitness,
+                    });
+                    best.truncate(limit);
+                    if position == 0 {
+                        let top = &best[0];
+                        monitor.best(format!("{} @ {} {} Crib: {} Fitness: {:.3}\nKey: {}\n{}", top.source, top.offset, top.family.name(), top.crib_matches, top.fitness, top.key, top.plaintext));
+                    }
+                }
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{
+        crypt::{K1p, K2p, K3p},
+        toolkit::periodic_encrypt_in,
+    };
+
+    /// Keeps the last best candidate a search reported.
+    #[derive(Default)]
+    struct Reported(Mutex<Option<String>>);
+
+    impl Monitor for Reported {
+        fn progress(&self, _done: usize, _total: usize) {}
+
+        fn best(&self, candidate: String) {
+            *self.0.lock().unwrap() = Some(candidate);
+        }
+
+        fn cancelled(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn search_finds_a_planted_offset_in_a_loaded_corpus() {
+        let dir = std::env::temp_dir().join(format!("krypton-running-key-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.txt"), K1p).unwrap();
+        fs::write(dir.join("b.txt"), K2p.to_lowercase()).unwrap();
+        fs::write(dir.join("c.txt"), format!("{}\n{}", &K3p[..100], &K3p[100..])).unwrap();
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        let corpus = load_corpus(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        let corpus = corpus.unwrap();
+        assert_eq!(corpus.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(corpus[1].1, K2p);
+        assert_eq!(corpus[2].1, K3p);
+
+        // The last key text holds the key, so its best must still be reported.
+        let alphabet = Alphabet::standard();
+        let plaintext = &K2p[..60];
+        let key = &K3p[123..183];
+        let ciphertext = periodic_encrypt_in(&alphabet, plaintext, Family::Beaufort, key);
+        let crib = format!("{}{}", &plaintext[..10], "_".repeat(50));
+        let monitor = Reported::default();
+        let found = running_key_search_monitored(&alphabet, &ciphertext, &Family::ALL, &corpus, &crib, 5, &monitor);
+        assert_eq!((found[0].source.as_str(), found[0].offset, found[0].family), ("c.txt", 123, Family::Beaufort));
+        assert_eq!(found[0].plaintext, plaintext);
+        assert!(monitor.0.lock().unwrap().as_deref().is_some_and(|best| best.starts_with("c.txt @ 123 ")));
+    }
+}
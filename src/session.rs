@@ -0,0 +1,107 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analysis::AnalysisResult,
+    anneal::AnnealConfig,
+    crib::{CribColumn, CribMatch},
+    error::{KryptonError, Result},
+    export::to_json,
+    significance::SignificanceConfig,
+};
+
+/// Version written into new session files. Files without a version load as version 0, and
+/// anything a file lacks takes its default, so older sessions still open.
+pub const SESSION_VERSION: u32 = 1;
+
+/// The buffers, keys, settings and results of one investigation, as saved to a session file
+/// and restored when the GUI starts again.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub version: u32,
+    pub encrypted: String,
+    pub plaintext: String,
+    pub key_length: usize,
+    /// Name of the selected cipher, so sessions survive changes to the order of `registry()`.
+    pub cipher: String,
+    pub keys: Vec<String>,
+    /// Index into `ALPHABETS`.
+    pub alphabet: usize,
+    pub custom_alphabet: String,
+    pub preserve_format: bool,
+    pub corpus_dir: String,
+    pub anneal: AnnealConfig,
+    pub excluded_factors: String,
+    pub significance: SignificanceConfig,
+    pub constraint_keywords: String,
+    pub crib: String,
+    pub crib_sort: CribColumn,
+    pub crib_descending: bool,
+    pub output: String,
+    pub terminals: [String; 4],
+    pub analysis: Option<AnalysisResult>,
+    pub crib_matches: Vec<CribMatch>,
+    /// Directory of the test case browser.
+    pub cases_dir: String,
+}
+
+pub fn save_session(path: &Path, session: &Session) -> Result<()> {
+    fs::write(path, to_json(session)?).map_err(|e| KryptonError::Io(format!("Could not write {}: {}", path.display(), e)))
+}
+
+/// Reads a session saved by `save_session`, refusing files from a newer version.
+pub fn load_session(path: &Path) -> Result<Session> {
+    let text = fs::read_to_string(path).map_err(|e| KryptonError::Io(format!("Could not read {}: {}", path.display(), e)))?;
+    let session: Session = serde_json::from_str(&text).map_err(|e| KryptonError::Invalid { input: "Session", reason: e.to_string() })?;
+    if session.version > SESSION_VERSION {
+        return Err(KryptonError::Invalid {
+            input: "Session",
+            reason: format!("version {} is newer than this build's {}", session.version, SESSION_VERSION),
+        });
+    }
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{alphabet::Alphabet, analysis::analyze, crib::crib_drag, crypt::{K1, K1p}};
+
+    #[test]
+    fn saved_sessions_load_unchanged() {
+        let session = Session {
+            version: SESSION_VERSION,
+            encrypted: K1.to_string(),
+            plaintext: K1p.to_string(),
+            key_length: 10,
+            cipher: "Keyed Vigenere".to_string(),
+            keys: vec!["KRYPTOS".to_string(), "PALIMPSEST".to_string()],
+            crib: "BETWEEN".to_string(),
+            crib_sort: CribColumn::Period,
+            crib_descending: true,
+            terminals: ["a".to_string(), "b".to_string(), String::new(), "d".to_string()],
+            analysis: Some(analyze(K1, K1p, 10, &[2], &Alphabet::standard(), &SignificanceConfig { trials: 3, ..SignificanceConfig::default() })),
+            crib_matches: crib_drag(K1, "BETWEEN", &Alphabet::standard(), "KRYPTOS"),
+            ..Session::default()
+        };
+        let path = std::env::temp_dir().join(format!("krypton-session-{}.json", std::process::id()));
+        save_session(&path, &session).unwrap();
+        let loaded = load_session(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(to_json(&loaded.unwrap()).unwrap(), to_json(&session).unwrap());
+    }
+
+    #[test]
+    fn old_sessions_load_and_newer_ones_are_refused() {
+        let session: Session = serde_json::from_str(r#"{"encrypted": "EMUFPHZ"}"#).unwrap();
+        assert_eq!((session.version, session.encrypted.as_str(), session.key_length), (0, "EMUFPHZ", 0));
+
+        let path = std::env::temp_dir().join(format!("krypton-newer-{}.json", std::process::id()));
+        fs::write(&path, format!(r#"{{"version": {}}}"#, SESSION_VERSION + 1)).unwrap();
+        let loaded = load_session(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    alphabet::Alphabet,
+    analysis::{statistic_values, AnalysisResult},
+    export::{known_name, StaticName},
+    jobs::{Monitor, Unmonitored},
+    toolkit::{periodic_encrypt_in, Family},
+};
+
+/// 20,000 letters of the corpus the n-gram tables were counted from; see `data/README.md`.
+const ENGLISH_SAMPLE: &str = include_str!("../data/english_sample.txt");
+
+/// Settings for the Monte Carlo baselines behind `significance`. The same seed always gives
+/// the same baselines, so results can be compared between runs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignificanceConfig {
+    pub trials: usize,
+    pub seed: u64,
+    /// Also compare against random periodic encryptions of English of the same length.
+    pub english: bool,
+}
+
+impl Default for SignificanceConfig {
+    fn default() -> Self {
+        SignificanceConfig { trials: 100, seed: 1, english: false }
+    }
+}
+
+/// Where one value falls in a baseline of simulated values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub mean: f64,
+    pub deviation: f64,
+    pub z: f64,
+    /// Two-sided share of trials at least as far from the mean as the value, counting the
+    /// value itself, so it is never 0.
+    pub p: f64,
+}
+
+impl Baseline {
+    fn new(value: f64, trials: &[f64]) -> Baseline {
+        let n = trials.len().max(1) as f64;
+        let mean = trials.iter().sum::<f64>() / n;
+        let deviation = (trials.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / n).sqrt();
+        // Statistics of letter counts alone do not change under shuffling; rounding noise
+        // in their spread must not turn into a z-score.
+        let constant = deviation <= 1e-9 * mean.abs().max(1.0);
+        let z = if constant { 0.0 } else { (value - mean) / deviation };
+        let extreme = trials.iter().filter(|t| (*t - mean).abs() >= (value - mean).abs()).count();
+        Baseline { mean, deviation, z, p: (extreme + 1) as f64 / (trials.len() + 1) as f64 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Significance {
+    #[serde(deserialize_with = "statistic_name")]
+    pub statistic: StaticName,
+    pub value: f64,
+    /// Against shuffles of the ciphertext's letters.
+    pub shuffled: Baseline,
+    /// Against periodic encryptions of English, when `SignificanceConfig::english` is set.
+    pub english: Option<Baseline>,
+}
+
+fn statistic_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<&'static str, D::Error> {
+    known_name(deserializer, &AnalysisResult::STATISTICS)
+}
+
+/// Shuffles the symbols of `alphabet` in `text` among their positions, leaving everything else put.
+fn shuffle_letters(text: &str, alphabet: &Alphabet, rng: &mut StdRng) -> String {
+    let mut letters: Vec<char> = text.chars().filter(|&c| alphabet.contains(c)).collect();
+    letters.shuffle(rng);
+    let mut letters = letters.into_iter();
+    text.chars().map(|c| if alphabet.contains(c) { letters.next().unwrap_or(c) } else { c }).collect()
+}
+
+/// A run of the English sample as long as `length`, from a random start, encrypted over
+/// `alphabet` with a random family and a random key of period 1 to `max_period`.
+fn english_encryption(length: usize, max_period: usize, alphabet: &Alphabet, rng: &mut StdRng) -> String {
+    let english: Vec<char> = ENGLISH_SAMPLE.chars().filter(|c| c.is_ascii_uppercase()).collect();
+    let start = rng.gen_range(0..english.len());
+    let plaintext: String = english.iter().cycle().skip(start).take(length).collect();
+    let family = Family::ALL[rng.gen_range(0..Family::ALL.len())];
+    let key: String = (0..rng.gen_range(1..=max_period.max(1))).map(|_| alphabet.symbol(rng.gen_range(0..alphabet.len()))).collect();
+    periodic_encrypt_in(alphabet, &plaintext, family, &key)
+}
+
+/// Z-score and p-value of every statistic in `result` against `config.trials` analyses of
+/// shuffled ciphertext and, if asked, of encrypted English, measured with the same
+/// plaintext, key length and excluded factors as `result`.
+pub fn significance(
+    result: &AnalysisResult,
+    encrypted_text: &str,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+    config: &SignificanceConfig,
+) -> Vec<Significance> {
+    significance_monitored(result, encrypted_text, plaintext, max_key_length, excluded_factors, alphabet, config, &Unmonitored)
+}
+
+/// `significance` reporting each trial as it is done. When cancelled, the baselines are
+/// made of the trials run up to then.
+#[allow(clippy::too_many_arguments)]
+pub fn significance_monitored(
+    result: &AnalysisResult,
+    encrypted_text: &str,
+    plaintext: &str,
+    max_key_length: usize,
+    excluded_factors: &[usize],
+    alphabet: &Alphabet,
+    config: &SignificanceConfig,
+    monitor: &dyn Monitor,
+) -> Vec<Significance> {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let total = config.trials * if config.english { 2 } else { 1 };
+    let mut done = 0;
+    let mut simulate = |text: &dyn Fn(&mut StdRng) -> String| -> Vec<Vec<f64>> {
+        let mut trials = Vec::with_capacity(config.trials);
+        for _ in 0..config.trials {
+            if monitor.cancelled() {
+                break;
+            }
+            monitor.progress(done, total);
+            done += 1;
+            let text = text(&mut rng);
+            trials.push(statistic_values(&text, plaintext, max_key_length, excluded_factors, alphabet).to_vec());
+        }
+        trials
+    };
+    let shuffled = simulate(&|rng| shuffle_letters(encrypted_text, alphabet, rng));
+    let length = alphabet.indices(encrypted_text).len();
+    let english = config.english.then(|| simulate(&|rng| english_encryption(length, max_key_length, alphabet, rng)));
+
+    let column = |trials: &[Vec<f64>], i: usize| -> Vec<f64> { trials.iter().map(|t| t[i]).collect() };
+    result
+        .statistics()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (statistic, value))| Significance {
+            statistic,
+            value,
+            shuffled: Baseline::new(value, &column(&shuffled, i)),
+            english: english.as_ref().map(|english| Baseline::new(value, &column(english, i))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze;
+
+    fn english(length: usize) -> String {
+        ENGLISH_SAMPLE.chars().filter(|c| c.is_ascii_uppercase()).take(length).collect()
+    }
+
+    fn p_value(text: &str, statistic: &str, config: &SignificanceConfig) -> Significance {
+        let alphabet = Alphabet::standard();
+        let result = analyze(text, "", 10, &[], &alphabet, &SignificanceConfig { trials: 0, ..config.clone() });
+        significance(&result, text, "", 10, &[], &alphabet, config)
+            .into_iter()
+            .find(|s| s.statistic == statistic)
+            .unwrap()
+    }
+
+    #[test]
+    fn shuffled_english_is_not_significant() {
+        let alphabet = Alphabet::standard();
+        let config = SignificanceConfig { trials: 40, seed: 7, english: false };
+        let mut rng = StdRng::seed_from_u64(3);
+        let p: Vec<f64> = (0..20)
+            .map(|_| {
+                let text = shuffle_letters(&english(300), &alphabet, &mut rng);
+                p_value(&text, "ACA DIC", &config).shuffled.p
+            })
+            .collect();
+        let mean = p.iter().sum::<f64>() / p.len() as f64;
+        assert!((0.35..0.65).contains(&mean), "mean p-value {mean}");
+        assert!(p.iter().filter(|&&p| p < 0.1).count() <= 5, "{p:?}");
+    }
+
+    #[test]
+    fn periodic_ciphertext_has_significant_coincidence() {
+        let text = periodic_encrypt_in(&Alphabet::standard(), &english(300), Family::Vigenere, "KRYPTOS");
+        let config = SignificanceConfig { trials: 40, seed: 7, english: false };
+        let significance = p_value(&text, "AVG INDEX OF COINCIDENCE", &config);
+        assert!(significance.shuffled.p < 0.05, "{significance:?}");
+        assert!(significance.shuffled.z > 3.0, "{significance:?}");
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_baselines() {
+        let text = english(200);
+        let config = SignificanceConfig { trials: 10, seed: 5, english: true };
+        assert_eq!(p_value(&text, "ACA LDI", &config), p_value(&text, "ACA LDI", &config));
+    }
+}
@@ -1,37 +1,18 @@
-use crate::vig2table;
+use serde::{Deserialize, Serialize};
 
+use crate::{
+    alphabet::Alphabet,
+    error::{check_key, check_range, require_key, require_text, KryptonError, Result},
+    decipher::vig2table,
+};
+
+/// Vigenere on a straight alphabet. Characters that are not letters are dropped.
 pub fn vigenere_one_encrypt(plaintext: &str, key: &str) -> String {
-    let key = if key.is_empty() {
-        "ABCDEFGHIJKLMNOPQRSTUVWXYZ".to_string()
-    } else {
-        key.to_string()
-    };
-    let key = key.chars().cycle();
-    plaintext.chars()
-        .zip(key)
-        .map(|(p, k)| {
-            let p = p as u8 - b'A';
-            let k = k as u8 - b'A';
-            (b'A' + (p + k) % 26) as char
-        })
-        .collect()
+    periodic_encrypt_in(&Alphabet::standard(), plaintext, Family::Vigenere, key)
 }
 
 pub fn vigenere_one_decrypt(ciphertext: &str, key: &str) -> String {
-    let key = if key.is_empty() {
-        "ABCDEFGHIJKLMNOPQRSTUVWXYZ".to_string()
-    } else {
-        key.to_string()
-    };
-    let key = key.chars().cycle();
-    ciphertext.chars()
-        .zip(key)
-        .map(|(c, k)| {
-            let c = c as u8 - b'A';
-            let k = k as u8 - b'A';
-            (b'A' + (c + 26 - k) % 26) as char
-        })
-        .collect()
+    periodic_decrypt_in(&Alphabet::standard(), ciphertext, Family::Vigenere, key)
 }
 
 pub fn vigenere_two_encrypt(plaintext: &str, key1: &str, key2: &str) -> String {
@@ -46,16 +27,7 @@ pub fn vigenere_two_encrypt(plaintext: &str, key1: &str, key2: &str) -> String {
         key2.to_string()
     };
     let table = vig2table(&key1, &key2);
-    let plaintext_chars: Vec<char> = plaintext.to_uppercase().chars().collect();
-    let mut encrypted_chars: Vec<char> = Vec::with_capacity(plaintext_chars.len());
-
-    for (index, &plain_char) in plaintext_chars.iter().enumerate() {
-        if let Some(position) = table[0].iter().position(|&c| c == plain_char) {
-            let wrapped_index = (index % (table.len() - 1)) + 1;
-            encrypted_chars.push(table[wrapped_index][position]);
-        }
-    }
-    encrypted_chars.into_iter().collect()
+    tableau_encrypt(&table, plaintext)
 }
 
 pub fn vigenere_two_decrypt(encrypted: &str, key1: &str, key2: &str) -> String {
@@ -70,6 +42,47 @@ pub fn vigenere_two_decrypt(encrypted: &str, key1: &str, key2: &str) -> String {
         key2.to_string()
     };
     let table = vig2table(&key1, &key2);
+    tableau_decrypt(&table, encrypted)
+}
+
+/// `vigenere_two_encrypt` over any alphabet. An empty `key2` reads the alphabet in order.
+pub fn vigenere_two_encrypt_in(alphabet: &Alphabet, plaintext: &str, key1: &str, key2: &str) -> String {
+    normalized_tableau_crypt(&keyed_tableau(alphabet, key1, key2), &alphabet.normalize(plaintext), false)
+}
+
+pub fn vigenere_two_decrypt_in(alphabet: &Alphabet, encrypted: &str, key1: &str, key2: &str) -> String {
+    normalized_tableau_crypt(&keyed_tableau(alphabet, key1, key2), &alphabet.normalize(encrypted), true)
+}
+
+fn keyed_tableau(alphabet: &Alphabet, key1: &str, key2: &str) -> Vec<Vec<char>> {
+    let keyed = alphabet.keyed(key1);
+    let key2 = if alphabet.indices(key2).is_empty() { alphabet.symbols().iter().collect() } else { key2.to_string() };
+    keyed.tableau(&key2)
+}
+
+/// Encrypts with a tableau whose first row is the plaintext alphabet and whose
+/// remaining rows are used in turn, one per letter. Letters not in the tableau are dropped,
+/// and a tableau without cipher rows gives nothing.
+pub fn tableau_encrypt(table: &[Vec<char>], plaintext: &str) -> String {
+    if table.len() < 2 {
+        return String::new();
+    }
+    let plaintext_chars: Vec<char> = plaintext.to_uppercase().chars().collect();
+    let mut encrypted_chars: Vec<char> = Vec::with_capacity(plaintext_chars.len());
+
+    for (index, &plain_char) in plaintext_chars.iter().enumerate() {
+        if let Some(position) = table[0].iter().position(|&c| c == plain_char) {
+            let wrapped_index = (index % (table.len() - 1)) + 1;
+            encrypted_chars.push(table[wrapped_index][position]);
+        }
+    }
+    encrypted_chars.into_iter().collect()
+}
+
+pub fn tableau_decrypt(table: &[Vec<char>], encrypted: &str) -> String {
+    if table.len() < 2 {
+        return String::new();
+    }
     let encrypted_chars: Vec<char> = encrypted.to_uppercase().chars().collect();
     let mut decrypted_chars: Vec<char> = Vec::with_capacity(encrypted_chars.len());
 
@@ -82,3 +95,818 @@ pub fn vigenere_two_decrypt(encrypted: &str, key1: &str, key2: &str) -> String {
     decrypted_chars.into_iter().collect()
 }
 
+/// `tableau_encrypt` or `tableau_decrypt` for text already normalized to the tableau's
+/// alphabet, matched exactly rather than in upper case.
+fn normalized_tableau_crypt(table: &[Vec<char>], text: &str, decrypt: bool) -> String {
+    text.chars()
+        .enumerate()
+        .filter_map(|(index, c)| {
+            let row = &table[(index % (table.len() - 1)) + 1];
+            if decrypt {
+                row.iter().position(|&r| r == c).map(|p| table[0][p])
+            } else {
+                table[0].iter().position(|&p| p == c).map(|p| row[p])
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quagmire {
+    /// Keyed plaintext alphabet, straight ciphertext alphabet.
+    I,
+    /// Straight plaintext alphabet, keyed ciphertext alphabet.
+    II,
+    /// The same keyed alphabet for plaintext and ciphertext.
+    III,
+    /// Independently keyed plaintext and ciphertext alphabets.
+    IV,
+}
+
+impl Quagmire {
+    pub const ALL: [Quagmire; 4] = [Quagmire::I, Quagmire::II, Quagmire::III, Quagmire::IV];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quagmire::I => "Quagmire I",
+            Quagmire::II => "Quagmire II",
+            Quagmire::III => "Quagmire III",
+            Quagmire::IV => "Quagmire IV",
+        }
+    }
+
+    /// Plaintext and ciphertext alphabets for this variant.
+    pub fn alphabets(&self, plain_key: &str, cipher_key: &str) -> (Vec<char>, Vec<char>) {
+        self.alphabets_in(&Alphabet::standard(), plain_key, cipher_key)
+    }
+
+    /// Plaintext and ciphertext alphabets for this variant, keyed from `alphabet`.
+    pub fn alphabets_in(&self, alphabet: &Alphabet, plain_key: &str, cipher_key: &str) -> (Vec<char>, Vec<char>) {
+        let keyed = |key: &str| alphabet.keyed(key).symbols().to_vec();
+        match self {
+            Quagmire::I => (keyed(plain_key), keyed("")),
+            Quagmire::II => (keyed(""), keyed(cipher_key)),
+            Quagmire::III => (keyed(plain_key), keyed(plain_key)),
+            Quagmire::IV => (keyed(plain_key), keyed(cipher_key)),
+        }
+    }
+}
+
+/// Builds a Quagmire tableau. Row 0 is the plaintext alphabet; row i+1 is the ciphertext
+/// alphabet slid so that the i-th indicator letter sits under the indicator position letter
+/// of the plaintext alphabet. An empty indicator position means the first plaintext letter,
+/// which makes Quagmire III with a KRYPTOS alphabet identical to `vig2table`.
+pub fn quagmire_table(
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> Vec<Vec<char>> {
+    quagmire_table_in(&Alphabet::standard(), variant, plain_key, cipher_key, indicator, indicator_position)
+}
+
+/// `quagmire_table` with both alphabets keyed from `alphabet` instead of A to Z.
+pub fn quagmire_table_in(
+    alphabet: &Alphabet,
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> Vec<Vec<char>> {
+    let (plain, cipher) = variant.alphabets_in(alphabet, plain_key, cipher_key);
+    let size = plain.len();
+    let column = indicator_position
+        .to_uppercase()
+        .chars()
+        .next()
+        .and_then(|p| alphabet.index(p))
+        .map(|p| alphabet.symbol(p))
+        .and_then(|p| plain.iter().position(|&c| c == p))
+        .unwrap_or(0);
+
+    let indicator: Vec<char> = indicator
+        .to_uppercase()
+        .chars()
+        .filter_map(|c| alphabet.index(c))
+        .map(|i| alphabet.symbol(i))
+        .collect();
+    let indicator = if indicator.is_empty() { vec![cipher[column]] } else { indicator };
+
+    let mut table = vec![plain];
+    for k in indicator {
+        let start = cipher.iter().position(|&c| c == k).unwrap_or(0) + size - column;
+        table.push((0..size).map(|j| cipher[(start + j) % size]).collect());
+    }
+    table
+}
+
+pub fn quagmire_encrypt(
+    plaintext: &str,
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> String {
+    let table = quagmire_table(variant, plain_key, cipher_key, indicator, indicator_position);
+    tableau_encrypt(&table, plaintext)
+}
+
+pub fn quagmire_decrypt(
+    encrypted: &str,
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> String {
+    let table = quagmire_table(variant, plain_key, cipher_key, indicator, indicator_position);
+    tableau_decrypt(&table, encrypted)
+}
+
+/// Quagmire encryption over `alphabet`. Text is normalized to the alphabet first, so
+/// merged and dropped symbols do not throw the indicator rows out of step.
+pub fn quagmire_encrypt_in(
+    alphabet: &Alphabet,
+    plaintext: &str,
+    variant: Quagmire,
+    keys: (&str, &str, &str, &str),
+) -> String {
+    let (plain_key, cipher_key, indicator, indicator_position) = keys;
+    let table = quagmire_table_in(alphabet, variant, plain_key, cipher_key, indicator, indicator_position);
+    normalized_tableau_crypt(&table, &alphabet.normalize(plaintext), false)
+}
+
+pub fn quagmire_decrypt_in(
+    alphabet: &Alphabet,
+    encrypted: &str,
+    variant: Quagmire,
+    keys: (&str, &str, &str, &str),
+) -> String {
+    let (plain_key, cipher_key, indicator, indicator_position) = keys;
+    let table = quagmire_table_in(alphabet, variant, plain_key, cipher_key, indicator, indicator_position);
+    normalized_tableau_crypt(&table, &alphabet.normalize(encrypted), true)
+}
+
+/// The three periodic tableau families. With plaintext index p, key index k and
+/// ciphertext index c: Vigenere c = p + k, Beaufort c = k - p, variant Beaufort c = p - k.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Family {
+    Vigenere,
+    Beaufort,
+    VariantBeaufort,
+}
+
+impl Family {
+    pub const ALL: [Family; 3] = [Family::Vigenere, Family::Beaufort, Family::VariantBeaufort];
+
+    /// The names of `ALL`, in the same order, for choices that index into it.
+    pub const NAMES: [&'static str; 3] = [Family::ALL[0].name(), Family::ALL[1].name(), Family::ALL[2].name()];
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Family::Vigenere => "Vigenere",
+            Family::Beaufort => "Beaufort",
+            Family::VariantBeaufort => "Variant Beaufort",
+        }
+    }
+
+    pub fn encrypt_index(&self, p: usize, k: usize) -> usize {
+        self.encrypt_index_in(p, k, 26)
+    }
+
+    pub fn decrypt_index(&self, c: usize, k: usize) -> usize {
+        self.decrypt_index_in(c, k, 26)
+    }
+
+    /// The key index that takes plaintext index `p` to ciphertext index `c`.
+    pub fn key_index(&self, p: usize, c: usize) -> usize {
+        self.key_index_in(p, c, 26)
+    }
+
+    /// `encrypt_index` for an alphabet of `size` symbols.
+    pub fn encrypt_index_in(&self, p: usize, k: usize, size: usize) -> usize {
+        match self {
+            Family::Vigenere => (p + k) % size,
+            Family::Beaufort => (k + size - p) % size,
+            Family::VariantBeaufort => (p + size - k) % size,
+        }
+    }
+
+    pub fn decrypt_index_in(&self, c: usize, k: usize, size: usize) -> usize {
+        match self {
+            Family::Vigenere => (c + size - k) % size,
+            Family::Beaufort => (k + size - c) % size,
+            Family::VariantBeaufort => (c + k) % size,
+        }
+    }
+
+    pub fn key_index_in(&self, p: usize, c: usize, size: usize) -> usize {
+        match self {
+            Family::Vigenere => (c + size - p) % size,
+            Family::Beaufort => (c + p) % size,
+            Family::VariantBeaufort => (p + size - c) % size,
+        }
+    }
+}
+
+fn periodic_crypt(text: &str, variant: Family, alphabet: &Alphabet, key: &str, decrypt: bool) -> String {
+    let mut key = alphabet.indices(key);
+    if key.is_empty() {
+        key = (0..alphabet.len()).collect();
+    }
+
+    alphabet
+        .indices(text)
+        .into_iter()
+        .enumerate()
+        .map(|(i, index)| {
+            let k = key[i % key.len()];
+            let out = if decrypt {
+                variant.decrypt_index_in(index, k, alphabet.len())
+            } else {
+                variant.encrypt_index_in(index, k, alphabet.len())
+            };
+            alphabet.symbol(out)
+        })
+        .collect()
+}
+
+/// Periodic encryption over the alphabet keyed by `key1` (straight when empty) with period key `key2`.
+/// Characters outside the alphabet are dropped.
+pub fn periodic_encrypt(plaintext: &str, variant: Family, key1: &str, key2: &str) -> String {
+    periodic_encrypt_in(&Alphabet::standard().keyed(key1), plaintext, variant, key2)
+}
+
+pub fn periodic_decrypt(ciphertext: &str, variant: Family, key1: &str, key2: &str) -> String {
+    periodic_decrypt_in(&Alphabet::standard().keyed(key1), ciphertext, variant, key2)
+}
+
+/// Periodic encryption over an already keyed `alphabet`.
+pub fn periodic_encrypt_in(alphabet: &Alphabet, plaintext: &str, variant: Family, key: &str) -> String {
+    periodic_crypt(plaintext, variant, alphabet, key, false)
+}
+
+pub fn periodic_decrypt_in(alphabet: &Alphabet, ciphertext: &str, variant: Family, key: &str) -> String {
+    periodic_crypt(ciphertext, variant, alphabet, key, true)
+}
+
+pub fn beaufort_one_encrypt(plaintext: &str, key: &str) -> String {
+    periodic_encrypt(plaintext, Family::Beaufort, "", key)
+}
+
+pub fn beaufort_one_decrypt(ciphertext: &str, key: &str) -> String {
+    periodic_decrypt(ciphertext, Family::Beaufort, "", key)
+}
+
+pub fn beaufort_two_encrypt(plaintext: &str, key1: &str, key2: &str) -> String {
+    periodic_encrypt(plaintext, Family::Beaufort, key1, key2)
+}
+
+pub fn beaufort_two_decrypt(ciphertext: &str, key1: &str, key2: &str) -> String {
+    periodic_decrypt(ciphertext, Family::Beaufort, key1, key2)
+}
+
+pub fn variant_beaufort_one_encrypt(plaintext: &str, key: &str) -> String {
+    periodic_encrypt(plaintext, Family::VariantBeaufort, "", key)
+}
+
+pub fn variant_beaufort_one_decrypt(ciphertext: &str, key: &str) -> String {
+    periodic_decrypt(ciphertext, Family::VariantBeaufort, "", key)
+}
+
+pub fn variant_beaufort_two_encrypt(plaintext: &str, key1: &str, key2: &str) -> String {
+    periodic_encrypt(plaintext, Family::VariantBeaufort, key1, key2)
+}
+
+pub fn variant_beaufort_two_decrypt(ciphertext: &str, key1: &str, key2: &str) -> String {
+    periodic_decrypt(ciphertext, Family::VariantBeaufort, key1, key2)
+}
+
+/// Which text extends the primer to form the autokey stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Autokey {
+    Plaintext,
+    Ciphertext,
+}
+
+impl Autokey {
+    pub const ALL: [Autokey; 2] = [Autokey::Plaintext, Autokey::Ciphertext];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Autokey::Plaintext => "Plaintext Autokey",
+            Autokey::Ciphertext => "Ciphertext Autokey",
+        }
+    }
+}
+
+fn autokey_crypt(text: &str, family: Family, mode: Autokey, alphabet: &Alphabet, primer: &str, decrypt: bool) -> String {
+    let size = alphabet.len();
+    let mut stream = alphabet.indices(primer);
+    if stream.is_empty() {
+        stream = (0..size).collect();
+    }
+
+    alphabet
+        .indices(text)
+        .into_iter()
+        .enumerate()
+        .map(|(i, index)| {
+            let k = stream[i];
+            let (plain, cipher) = if decrypt {
+                (family.decrypt_index_in(index, k, size), index)
+            } else {
+                (index, family.encrypt_index_in(index, k, size))
+            };
+            stream.push(if mode == Autokey::Plaintext { plain } else { cipher });
+            alphabet.symbol(if decrypt { plain } else { cipher })
+        })
+        .collect()
+}
+
+/// Autokey encryption: the primer keyword is followed by the plaintext or the ciphertext
+/// as key stream. `key1` keys the tableau alphabet as in `vig2table` (straight when empty).
+pub fn autokey_encrypt(plaintext: &str, family: Family, mode: Autokey, key1: &str, primer: &str) -> String {
+    autokey_encrypt_in(&Alphabet::standard().keyed(key1), plaintext, family, mode, primer)
+}
+
+pub fn autokey_decrypt(ciphertext: &str, family: Family, mode: Autokey, key1: &str, primer: &str) -> String {
+    autokey_decrypt_in(&Alphabet::standard().keyed(key1), ciphertext, family, mode, primer)
+}
+
+/// Autokey over an already keyed `alphabet`.
+pub fn autokey_encrypt_in(alphabet: &Alphabet, plaintext: &str, family: Family, mode: Autokey, primer: &str) -> String {
+    autokey_crypt(plaintext, family, mode, alphabet, primer, false)
+}
+
+pub fn autokey_decrypt_in(alphabet: &Alphabet, ciphertext: &str, family: Family, mode: Autokey, primer: &str) -> String {
+    autokey_crypt(ciphertext, family, mode, alphabet, primer, true)
+}
+
+/// Running key: the key is a passage of text, read from `offset` onwards, instead of a
+/// repeating keyword. `key1` keys the tableau alphabet as in `vig2table`.
+pub fn running_key_encrypt(plaintext: &str, family: Family, key1: &str, key_text: &str, offset: usize) -> String {
+    running_key_encrypt_in(&Alphabet::standard().keyed(key1), plaintext, family, key_text, offset)
+}
+
+pub fn running_key_decrypt(ciphertext: &str, family: Family, key1: &str, key_text: &str, offset: usize) -> String {
+    running_key_decrypt_in(&Alphabet::standard().keyed(key1), ciphertext, family, key_text, offset)
+}
+
+/// Running key over an already keyed `alphabet`; `offset` counts symbols of the alphabet.
+pub fn running_key_encrypt_in(alphabet: &Alphabet, plaintext: &str, family: Family, key_text: &str, offset: usize) -> String {
+    let key: String = key_text.chars().filter(|&c| alphabet.contains(c)).skip(offset).collect();
+    periodic_encrypt_in(alphabet, plaintext, family, &key)
+}
+
+pub fn running_key_decrypt_in(alphabet: &Alphabet, ciphertext: &str, family: Family, key_text: &str, offset: usize) -> String {
+    let key: String = key_text.chars().filter(|&c| alphabet.contains(c)).skip(offset).collect();
+    periodic_decrypt_in(alphabet, ciphertext, family, &key)
+}
+
+/// Reading order of the columns for a columnar transposition key. A keyword numbers its
+/// columns alphabetically, repeated letters left to right; a comma separated list such as
+/// `3,1,2` gives the numbers directly. Returns column indices in the order they are read.
+pub fn column_order(key: &str) -> Vec<usize> {
+    let ranks: Vec<usize> = if key.contains(',') {
+        key.split(',').filter_map(|n| n.trim().parse().ok()).collect()
+    } else {
+        key.to_uppercase().chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c as usize).collect()
+    };
+    let mut order: Vec<usize> = (0..ranks.len()).collect();
+    order.sort_by_key(|&column| (ranks[column], column));
+    order
+}
+
+/// The comma separated key for a reading order, the inverse of `column_order`.
+pub fn order_key(order: &[usize]) -> String {
+    let mut ranks = vec![0; order.len()];
+    for (rank, &column) in order.iter().enumerate() {
+        ranks[column] = rank + 1;
+    }
+    ranks.iter().map(|r| r.to_string()).collect::<Vec<String>>().join(",")
+}
+
+/// Writes the text in rows as wide as the key and reads the columns off in key order.
+/// The last row may be short; whitespace is dropped and every other character kept.
+pub fn columnar_encrypt(plaintext: &str, order: &[usize]) -> String {
+    let text: Vec<char> = plaintext.chars().filter(|c| !c.is_whitespace()).collect();
+    if order.is_empty() {
+        return text.into_iter().collect();
+    }
+    order
+        .iter()
+        .flat_map(|&column| text.iter().skip(column).step_by(order.len()))
+        .collect()
+}
+
+pub fn columnar_decrypt(ciphertext: &str, order: &[usize]) -> String {
+    let text: Vec<char> = ciphertext.chars().filter(|c| !c.is_whitespace()).collect();
+    let width = order.len();
+    if width == 0 {
+        return text.into_iter().collect();
+    }
+    let (rows, long_columns) = (text.len() / width, text.len() % width);
+    let mut plain = vec![' '; text.len()];
+    let mut index = 0;
+    for &column in order {
+        let height = rows + usize::from(column < long_columns);
+        for row in 0..height {
+            plain[row * width + column] = text[index];
+            index += 1;
+        }
+    }
+    plain.into_iter().collect()
+}
+
+/// Which way the grid is turned in a rotating-grid transposition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rotation {
+    Clockwise,
+    Counterclockwise,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 2] = [Rotation::Clockwise, Rotation::Counterclockwise];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rotation::Clockwise => "Clockwise",
+            Rotation::Counterclockwise => "Counterclockwise",
+        }
+    }
+}
+
+/// Source index of every character after writing `len` characters in rows of `width`,
+/// turning the grid a quarter and reading its rows. Cells missing from a short last row
+/// are skipped.
+fn rotation_indices(len: usize, width: usize, rotation: Rotation) -> Vec<usize> {
+    let width = width.clamp(1, len.max(1));
+    let rows = len.div_ceil(width);
+    let columns: Vec<usize> = match rotation {
+        Rotation::Clockwise => (0..width).collect(),
+        Rotation::Counterclockwise => (0..width).rev().collect(),
+    };
+    columns
+        .into_iter()
+        .flat_map(|column| {
+            let cells = (0..rows).map(move |row| row * width + column);
+            let cells: Vec<usize> = match rotation {
+                Rotation::Clockwise => cells.rev().collect(),
+                Rotation::Counterclockwise => cells.collect(),
+            };
+            cells.into_iter().filter(|&i| i < len)
+        })
+        .collect()
+}
+
+/// Where each plaintext character comes from in the ciphertext: rotate at `width1`, then
+/// rewrite at `width2` and rotate again.
+fn rotating_grid_indices(len: usize, width1: usize, width2: usize, rotation: Rotation) -> Vec<usize> {
+    let first = rotation_indices(len, width1, rotation);
+    rotation_indices(len, width2, rotation).into_iter().map(|i| first[i]).collect()
+}
+
+/// K3 style transposition, undone by writing the ciphertext in rows of `width1`, turning
+/// the grid, rewriting it in rows of `width2` and turning it again. K3 is `24, 8` clockwise.
+/// Whitespace is dropped and every other character kept.
+pub fn rotating_grid_decrypt(ciphertext: &str, width1: usize, width2: usize, rotation: Rotation) -> String {
+    let text: Vec<char> = ciphertext.chars().filter(|c| !c.is_whitespace()).collect();
+    rotating_grid_indices(text.len(), width1, width2, rotation).into_iter().map(|i| text[i]).collect()
+}
+
+pub fn rotating_grid_encrypt(plaintext: &str, width1: usize, width2: usize, rotation: Rotation) -> String {
+    let text: Vec<char> = plaintext.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut cipher = vec![' '; text.len()];
+    for (&source, &c) in rotating_grid_indices(text.len(), width1, width2, rotation).iter().zip(&text) {
+        cipher[source] = c;
+    }
+    cipher.into_iter().collect()
+}
+
+// Checked versions of the functions above. They report input that the unchecked versions
+// would quietly drop, pad or turn into empty output.
+
+/// A tableau needs a plaintext row and at least one cipher row, all as long as the first
+/// and with no symbol repeated in the plaintext row. Gives the plaintext row as an alphabet.
+pub(crate) fn check_tableau(table: &[Vec<char>]) -> Result<Alphabet> {
+    if table.len() < 2 {
+        return Err(KryptonError::Invalid { input: "Table", reason: format!("needs at least 2 rows, has {}", table.len()) });
+    }
+    if let Some((row, cells)) = table.iter().enumerate().find(|(_, row)| row.len() != table[0].len()) {
+        return Err(KryptonError::Invalid { input: "Table", reason: format!("row {} has {} symbols, row 0 has {}", row, cells.len(), table[0].len()) });
+    }
+    Alphabet::custom(&table[0].iter().collect::<String>())
+}
+
+/// A reading order must list every column from 0 once.
+fn check_order(order: &[usize]) -> Result<()> {
+    if order.is_empty() {
+        return Err(KryptonError::EmptyKey("Column Order"));
+    }
+    let mut seen = vec![false; order.len()];
+    for &column in order {
+        if column >= order.len() || seen[column] {
+            return Err(KryptonError::Invalid { input: "Column Order", reason: format!("column {} is missing or repeated", column) });
+        }
+        seen[column] = true;
+    }
+    Ok(())
+}
+
+fn check_quagmire_keys(alphabet: &Alphabet, keys: (&str, &str, &str, &str)) -> Result<()> {
+    let (plain_key, cipher_key, indicator, indicator_position) = keys;
+    check_key("Plain Key", plain_key, alphabet)?;
+    check_key("Cipher Key", cipher_key, alphabet)?;
+    check_key("Indicator", indicator, alphabet)?;
+    check_key("Indicator Position", indicator_position, alphabet)?;
+    let found = indicator_position.chars().filter(|c| !c.is_whitespace()).count();
+    if found > 1 {
+        return Err(KryptonError::Invalid { input: "Indicator Position", reason: format!("expected one symbol, found {}", found) });
+    }
+    Ok(())
+}
+
+fn check_running_key(alphabet: &Alphabet, text: &str, key_text: &str, offset: usize) -> Result<()> {
+    let needed = alphabet.indices(text).len() + offset;
+    let found = alphabet.indices(key_text).len();
+    if found < needed {
+        return Err(KryptonError::TooShort { input: "Key Text", needed, found });
+    }
+    Ok(())
+}
+
+fn check_grid_widths(text: &str, width1: usize, width2: usize) -> Result<()> {
+    let length = text.chars().filter(|c| !c.is_whitespace()).count();
+    if length == 0 {
+        return Err(KryptonError::EmptyText("Text"));
+    }
+    check_range("Width 1", width1, 1, length)?;
+    check_range("Width 2", width2, 1, length)
+}
+
+pub fn try_vigenere_one_encrypt(plaintext: &str, key: &str) -> Result<String> {
+    try_periodic_encrypt_in(&Alphabet::standard(), plaintext, Family::Vigenere, key)
+}
+
+pub fn try_vigenere_one_decrypt(ciphertext: &str, key: &str) -> Result<String> {
+    try_periodic_decrypt_in(&Alphabet::standard(), ciphertext, Family::Vigenere, key)
+}
+
+pub fn try_vigenere_two_encrypt(plaintext: &str, key1: &str, key2: &str) -> Result<String> {
+    try_vigenere_two_encrypt_in(&Alphabet::standard(), plaintext, key1, key2)
+}
+
+pub fn try_vigenere_two_decrypt(encrypted: &str, key1: &str, key2: &str) -> Result<String> {
+    try_vigenere_two_decrypt_in(&Alphabet::standard(), encrypted, key1, key2)
+}
+
+pub fn try_vigenere_two_encrypt_in(alphabet: &Alphabet, plaintext: &str, key1: &str, key2: &str) -> Result<String> {
+    require_text("Plaintext", plaintext, alphabet)?;
+    check_key("Key 1", key1, alphabet)?;
+    check_key("Key 2", key2, alphabet)?;
+    Ok(vigenere_two_encrypt_in(alphabet, plaintext, key1, key2))
+}
+
+pub fn try_vigenere_two_decrypt_in(alphabet: &Alphabet, encrypted: &str, key1: &str, key2: &str) -> Result<String> {
+    require_text("Encrypted", encrypted, alphabet)?;
+    check_key("Key 1", key1, alphabet)?;
+    check_key("Key 2", key2, alphabet)?;
+    Ok(vigenere_two_decrypt_in(alphabet, encrypted, key1, key2))
+}
+
+pub fn try_tableau_encrypt(table: &[Vec<char>], plaintext: &str) -> Result<String> {
+    require_text("Plaintext", plaintext, &check_tableau(table)?)?;
+    Ok(tableau_encrypt(table, plaintext))
+}
+
+pub fn try_tableau_decrypt(table: &[Vec<char>], encrypted: &str) -> Result<String> {
+    require_text("Encrypted", encrypted, &check_tableau(table)?)?;
+    Ok(tableau_decrypt(table, encrypted))
+}
+
+pub fn try_quagmire_table(
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> Result<Vec<Vec<char>>> {
+    try_quagmire_table_in(&Alphabet::standard(), variant, plain_key, cipher_key, indicator, indicator_position)
+}
+
+pub fn try_quagmire_table_in(
+    alphabet: &Alphabet,
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> Result<Vec<Vec<char>>> {
+    check_quagmire_keys(alphabet, (plain_key, cipher_key, indicator, indicator_position))?;
+    Ok(quagmire_table_in(alphabet, variant, plain_key, cipher_key, indicator, indicator_position))
+}
+
+pub fn try_quagmire_encrypt(
+    plaintext: &str,
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> Result<String> {
+    try_quagmire_encrypt_in(&Alphabet::standard(), plaintext, variant, (plain_key, cipher_key, indicator, indicator_position))
+}
+
+pub fn try_quagmire_decrypt(
+    encrypted: &str,
+    variant: Quagmire,
+    plain_key: &str,
+    cipher_key: &str,
+    indicator: &str,
+    indicator_position: &str,
+) -> Result<String> {
+    try_quagmire_decrypt_in(&Alphabet::standard(), encrypted, variant, (plain_key, cipher_key, indicator, indicator_position))
+}
+
+pub fn try_quagmire_encrypt_in(
+    alphabet: &Alphabet,
+    plaintext: &str,
+    variant: Quagmire,
+    keys: (&str, &str, &str, &str),
+) -> Result<String> {
+    require_text("Plaintext", plaintext, alphabet)?;
+    check_quagmire_keys(alphabet, keys)?;
+    Ok(quagmire_encrypt_in(alphabet, plaintext, variant, keys))
+}
+
+pub fn try_quagmire_decrypt_in(
+    alphabet: &Alphabet,
+    encrypted: &str,
+    variant: Quagmire,
+    keys: (&str, &str, &str, &str),
+) -> Result<String> {
+    require_text("Encrypted", encrypted, alphabet)?;
+    check_quagmire_keys(alphabet, keys)?;
+    Ok(quagmire_decrypt_in(alphabet, encrypted, variant, keys))
+}
+
+pub fn try_periodic_encrypt(plaintext: &str, variant: Family, key1: &str, key2: &str) -> Result<String> {
+    check_key("Key 1", key1, &Alphabet::standard())?;
+    try_periodic_encrypt_in(&Alphabet::standard().keyed(key1), plaintext, variant, key2)
+}
+
+pub fn try_periodic_decrypt(ciphertext: &str, variant: Family, key1: &str, key2: &str) -> Result<String> {
+    check_key("Key 1", key1, &Alphabet::standard())?;
+    try_periodic_decrypt_in(&Alphabet::standard().keyed(key1), ciphertext, variant, key2)
+}
+
+/// Unlike `periodic_encrypt_in`, an empty period key is an error rather than the whole alphabet.
+pub fn try_periodic_encrypt_in(alphabet: &Alphabet, plaintext: &str, variant: Family, key: &str) -> Result<String> {
+    require_text("Plaintext", plaintext, alphabet)?;
+    require_key("Key", key, alphabet)?;
+    Ok(periodic_encrypt_in(alphabet, plaintext, variant, key))
+}
+
+pub fn try_periodic_decrypt_in(alphabet: &Alphabet, ciphertext: &str, variant: Family, key: &str) -> Result<String> {
+    require_text("Encrypted", ciphertext, alphabet)?;
+    require_key("Key", key, alphabet)?;
+    Ok(periodic_decrypt_in(alphabet, ciphertext, variant, key))
+}
+
+pub fn try_beaufort_one_encrypt(plaintext: &str, key: &str) -> Result<String> {
+    try_periodic_encrypt(plaintext, Family::Beaufort, "", key)
+}
+
+pub fn try_beaufort_one_decrypt(ciphertext: &str, key: &str) -> Result<String> {
+    try_periodic_decrypt(ciphertext, Family::Beaufort, "", key)
+}
+
+pub fn try_beaufort_two_encrypt(plaintext: &str, key1: &str, key2: &str) -> Result<String> {
+    try_periodic_encrypt(plaintext, Family::Beaufort, key1, key2)
+}
+
+pub fn try_beaufort_two_decrypt(ciphertext: &str, key1: &str, key2: &str) -> Result<String> {
+    try_periodic_decrypt(ciphertext, Family::Beaufort, key1, key2)
+}
+
+pub fn try_variant_beaufort_one_encrypt(plaintext: &str, key: &str) -> Result<String> {
+    try_periodic_encrypt(plaintext, Family::VariantBeaufort, "", key)
+}
+
+pub fn try_variant_beaufort_one_decrypt(ciphertext: &str, key: &str) -> Result<String> {
+    try_periodic_decrypt(ciphertext, Family::VariantBeaufort, "", key)
+}
+
+pub fn try_variant_beaufort_two_encrypt(plaintext: &str, key1: &str, key2: &str) -> Result<String> {
+    try_periodic_encrypt(plaintext, Family::VariantBeaufort, key1, key2)
+}
+
+pub fn try_variant_beaufort_two_decrypt(ciphertext: &str, key1: &str, key2: &str) -> Result<String> {
+    try_periodic_decrypt(ciphertext, Family::VariantBeaufort, key1, key2)
+}
+
+pub fn try_autokey_encrypt(plaintext: &str, family: Family, mode: Autokey, key1: &str, primer: &str) -> Result<String> {
+    check_key("Key 1", key1, &Alphabet::standard())?;
+    try_autokey_encrypt_in(&Alphabet::standard().keyed(key1), plaintext, family, mode, primer)
+}
+
+pub fn try_autokey_decrypt(ciphertext: &str, family: Family, mode: Autokey, key1: &str, primer: &str) -> Result<String> {
+    check_key("Key 1", key1, &Alphabet::standard())?;
+    try_autokey_decrypt_in(&Alphabet::standard().keyed(key1), ciphertext, family, mode, primer)
+}
+
+pub fn try_autokey_encrypt_in(alphabet: &Alphabet, plaintext: &str, family: Family, mode: Autokey, primer: &str) -> Result<String> {
+    require_text("Plaintext", plaintext, alphabet)?;
+    require_key("Primer", primer, alphabet)?;
+    Ok(autokey_encrypt_in(alphabet, plaintext, family, mode, primer))
+}
+
+pub fn try_autokey_decrypt_in(alphabet: &Alphabet, ciphertext: &str, family: Family, mode: Autokey, primer: &str) -> Result<String> {
+    require_text("Encrypted", ciphertext, alphabet)?;
+    require_key("Primer", primer, alphabet)?;
+    Ok(autokey_decrypt_in(alphabet, ciphertext, family, mode, primer))
+}
+
+pub fn try_running_key_encrypt(plaintext: &str, family: Family, key1: &str, key_text: &str, offset: usize) -> Result<String> {
+    check_key("Key 1", key1, &Alphabet::standard())?;
+    try_running_key_encrypt_in(&Alphabet::standard().keyed(key1), plaintext, family, key_text, offset)
+}
+
+pub fn try_running_key_decrypt(ciphertext: &str, family: Family, key1: &str, key_text: &str, offset: usize) -> Result<String> {
+    check_key("Key 1", key1, &Alphabet::standard())?;
+    try_running_key_decrypt_in(&Alphabet::standard().keyed(key1), ciphertext, family, key_text, offset)
+}
+
+/// The key text after `offset` must cover the whole text; the unchecked version repeats it.
+pub fn try_running_key_encrypt_in(alphabet: &Alphabet, plaintext: &str, family: Family, key_text: &str, offset: usize) -> Result<String> {
+    require_text("Plaintext", plaintext, alphabet)?;
+    check_running_key(alphabet, plaintext, key_text, offset)?;
+    Ok(running_key_encrypt_in(alphabet, plaintext, family, key_text, offset))
+}
+
+pub fn try_running_key_decrypt_in(alphabet: &Alphabet, ciphertext: &str, family: Family, key_text: &str, offset: usize) -> Result<String> {
+    require_text("Encrypted", ciphertext, alphabet)?;
+    check_running_key(alphabet, ciphertext, key_text, offset)?;
+    Ok(running_key_decrypt_in(alphabet, ciphertext, family, key_text, offset))
+}
+
+/// A numbered key must use each number from 1 to its width once; a keyword needs a letter
+/// or digit.
+pub fn try_column_order(key: &str) -> Result<Vec<usize>> {
+    if !key.contains(',') {
+        if !key.chars().any(|c| c.is_ascii_alphanumeric()) {
+            return Err(KryptonError::EmptyKey("Column Order"));
+        }
+        return Ok(column_order(key));
+    }
+    let mut ranks = Vec::new();
+    for rank in key.split(',').map(str::trim) {
+        let rank = rank
+            .parse::<usize>()
+            .map_err(|_| KryptonError::Invalid { input: "Column Order", reason: format!("'{}' is not a number", rank) })?;
+        ranks.push(rank);
+    }
+    let mut sorted = ranks.clone();
+    sorted.sort_unstable();
+    if sorted != (1..=ranks.len()).collect::<Vec<usize>>() {
+        return Err(KryptonError::Invalid { input: "Column Order", reason: format!("expected each of 1 to {} once", ranks.len()) });
+    }
+    Ok(column_order(key))
+}
+
+pub fn try_order_key(order: &[usize]) -> Result<String> {
+    check_order(order)?;
+    Ok(order_key(order))
+}
+
+pub fn try_columnar_encrypt(plaintext: &str, order: &[usize]) -> Result<String> {
+    if plaintext.chars().all(char::is_whitespace) {
+        return Err(KryptonError::EmptyText("Plaintext"));
+    }
+    check_order(order)?;
+    Ok(columnar_encrypt(plaintext, order))
+}
+
+pub fn try_columnar_decrypt(ciphertext: &str, order: &[usize]) -> Result<String> {
+    if ciphertext.chars().all(char::is_whitespace) {
+        return Err(KryptonError::EmptyText("Encrypted"));
+    }
+    check_order(order)?;
+    Ok(columnar_decrypt(ciphertext, order))
+}
+
+/// Widths must lie between 1 and the text length; the unchecked versions clamp them.
+pub fn try_rotating_grid_decrypt(ciphertext: &str, width1: usize, width2: usize, rotation: Rotation) -> Result<String> {
+    check_grid_widths(ciphertext, width1, width2)?;
+    Ok(rotating_grid_decrypt(ciphertext, width1, width2, rotation))
+}
+
+pub fn try_rotating_grid_encrypt(plaintext: &str, width1: usize, width2: usize, rotation: Rotation) -> Result<String> {
+    check_grid_widths(plaintext, width1, width2)?;
+    Ok(rotating_grid_encrypt(plaintext, width1, width2, rotation))
+}
@@ -0,0 +1,222 @@
+use itertools::Itertools;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+
+use crate::{
+    jobs::{Monitor, Unmonitored},
+    ngram::{english_fitness, NgramModel},
+    toolkit::{columnar_decrypt, order_key, rotating_grid_decrypt, Rotation},
+};
+
+#[derive(Serialize)]
+pub struct ColumnarCandidate {
+    pub width: usize,
+    pub order: Vec<usize>,
+    pub key: String,
+    pub plaintext: String,
+    pub fitness: f64,
+}
+
+#[derive(Serialize)]
+pub struct RotationCandidate {
+    pub width1: usize,
+    pub width2: usize,
+    pub rotation: Rotation,
+    pub plaintext: String,
+    pub crib_matches: usize,
+    pub fitness: f64,
+}
+
+/// Widths up to this are searched exhaustively, wider ones by hill climbing.
+const EXHAUSTIVE_WIDTH: usize = 8;
+
+/// Searches column orders for every width from 2 to `max_width` and returns the best order
+/// found at each width, ranked by quadgram fitness. Widths above eight columns are hill
+/// climbed from `restarts` random orders, so the result for them depends on `seed`.
+pub fn columnar_solve(ciphertext: &str, max_width: usize, restarts: usize, seed: u64) -> Vec<ColumnarCandidate> {
+    columnar_solve_monitored(ciphertext, max_width, restarts, seed, &Unmonitored)
+}
+
+/// `columnar_solve` reporting each width as it is done with the best candidate so far,
+/// and returning the widths done when cancelled.
+pub fn columnar_solve_monitored(ciphertext: &str, max_width: usize, restarts: usize, seed: u64, monitor: &dyn Monitor) -> Vec<ColumnarCandidate> {
+    let model = NgramModel::english(4).expect("built-in quadgrams");
+    let text: String = ciphertext.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut candidates = Vec::new();
+
+    let widths = 2..=max_width.min(text.chars().count());
+    let total = widths.clone().count();
+    for (done, width) in widths.enumerate() {
+        if monitor.cancelled() {
+            break;
+        }
+        monitor.progress(done, total);
+        let score = |order: &[usize]| model.score(&columnar_decrypt(&text, order));
+        let (order, _) = if width <= EXHAUSTIVE_WIDTH {
+            (0..width)
+                .permutations(width)
+                .map(|order| {
+                    let score = score(&order);
+                    (order, score)
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .expect("at least one order")
+        } else {
+            (0..restarts.max(1))
+                .map(|_| climb(width, &mut rng, &score))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .expect("at least one restart")
+        };
+        let plaintext = columnar_decrypt(&text, &order);
+        candidates.push(ColumnarCandidate {
+            width,
+            key: order_key(&order),
+            fitness: model.fitness(&plaintext),
+            order,
+            plaintext,
+        });
+        if let Some(best) = candidates.iter().max_by(|a, b| a.fitness.total_cmp(&b.fitness)) {
+            monitor.best(format!("Width: {} Key: {} Fitness: {:.3}\n{}", best.width, best.key, best.fitness, best.plaintext));
+        }
+    }
+    candidates.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+    candidates
+}
+
+/// Hill climbs one random order by swapping two columns, moving or reversing a run of
+/// columns, until a long stretch of moves brings no improvement.
+fn climb(width: usize, rng: &mut StdRng, score: &impl Fn(&[usize]) -> f64) -> (Vec<usize>, f64) {
+    let mut order: Vec<usize> = (0..width).collect();
+    for i in (1..width).rev() {
+        order.swap(i, rng.gen_range(0..=i));
+    }
+    let mut best = score(&order);
+    let mut stale = 0;
+    while stale < 20 * width * width {
+        let mut candidate = order.clone();
+        let i = rng.gen_range(0..width);
+        let j = rng.gen_range(0..width);
+        match rng.gen_range(0..3) {
+            0 => candidate.swap(i, j),
+            1 => {
+                let run: Vec<usize> = candidate.drain(i.min(j)..=i.max(j)).collect();
+                let at = rng.gen_range(0..=candidate.len());
+                candidate.splice(at..at, run);
+            }
+            _ => candidate[i.min(j)..=i.max(j)].reverse(),
+        }
+        let candidate_score = score(&candidate);
+        if candidate_score > best {
+            order = candidate;
+            best = candidate_score;
+            stale = 0;
+        } else {
+            stale += 1;
+        }
+    }
+    (order, best)
+}
+
+/// Tries every pair of widths from 2 to `max_width` in both directions and keeps the
+/// `limit` best, skipping pairs that give a plaintext already kept. Candidates are ranked
+/// by how many letters of the crib (aligned with the ciphertext, `_` for unknown, like
+/// `K3p`) they reproduce, then by quadgram fitness.
+pub fn rotating_grid_search(ciphertext: &str, crib: &str, max_width: usize, limit: usize) -> Vec<RotationCandidate> {
+    rotating_grid_search_monitored(ciphertext, crib, max_width, limit, &Unmonitored)
+}
+
+/// `rotating_grid_search` reporting each first width as it is done with the best candidate
+/// so far, and returning what it has when cancelled.
+pub fn rotating_grid_search_monitored(ciphertext: &str, crib: &str, max_width: usize, limit: usize, monitor: &dyn Monitor) -> Vec<RotationCandidate> {
+    let crib: Vec<char> = crib.to_uppercase().chars().filter(|c| !c.is_whitespace()).collect();
+    let mut best: Vec<RotationCandidate> = Vec::with_capacity(limit + 1);
+    if limit == 0 {
+        return best;
+    }
+    for width1 in 2..=max_width {
+        if monitor.cancelled() {
+            break;
+        }
+        monitor.progress(width1 - 2, max_width - 1);
+        for width2 in 2..=max_width {
+            for rotation in Rotation::ALL {
+                let plaintext = rotating_grid_decrypt(ciphertext, width1, width2, rotation);
+                if best.iter().any(|b| b.plaintext == plaintext) {
+                    continue;
+                }
+                let crib_matches = plaintext
+                    .chars()
+                    .zip(&crib)
+                    .filter(|&(p, &c)| c.is_ascii_alphabetic() && p.to_ascii_uppercase() == c)
+                    .count();
+                let candidate = RotationCandidate {
+                    width1,
+                    width2,
+                    rotation,
+                    fitness: english_fitness(&plaintext),
+                    plaintext,
+                    crib_matches,
+                };
+                let position = best
+                    .iter()
+                    .position(|b| (candidate.crib_matches, candidate.fitness) > (b.crib_matches, b.fitness))
+                    .unwrap_or(best.len());
+                best.insert(position, candidate);
+                best.truncate(limit);
+            }
+        }
+        if let Some(top) = best.first() {
+            monitor.best(format!("Widths: {} {} {} Crib: {} Fitness: {:.3}\n{}", top.width1, top.width2, top.rotation.name(), top.crib_matches, top.fitness, top.plaintext));
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crypt::{K2p, K3, K3p},
+        toolkit::{column_order, columnar_encrypt, rotating_grid_encrypt},
+    };
+
+    #[test]
+    fn columnar_solve_recovers_a_narrow_key_exhaustively() {
+        let order = column_order("ZEBRAS");
+        let best = &columnar_solve(&columnar_encrypt(K2p, &order), 7, 1, 1)[0];
+        assert_eq!((best.width, &best.order), (6, &order));
+        assert_eq!(best.plaintext, K2p);
+    }
+
+    #[test]
+    fn columnar_solve_recovers_a_wide_key_by_hill_climbing() {
+        let order = column_order("CRYPTOGRAM");
+        let best = &columnar_solve(&columnar_encrypt(K2p, &order), 10, 8, 1)[0];
+        assert_eq!((best.width, &best.order), (10, &order));
+        assert_eq!(best.plaintext, K2p);
+    }
+
+    #[test]
+    fn rotating_grid_round_trips() {
+        for rotation in Rotation::ALL {
+            for (width1, width2) in [(24, 8), (7, 11), (5, 5)] {
+                let ciphertext = rotating_grid_encrypt(K3p, width1, width2, rotation);
+                assert_ne!(ciphertext, K3p);
+                assert_eq!(rotating_grid_decrypt(&ciphertext, width1, width2, rotation), K3p, "{width1} {width2} {rotation:?}");
+            }
+        }
+        assert_eq!(rotating_grid_decrypt(K3, 24, 8, Rotation::Clockwise), K3p);
+    }
+
+    #[test]
+    fn rotating_grid_search_finds_the_widths() {
+        let ciphertext = rotating_grid_encrypt(K3p, 9, 13, Rotation::Counterclockwise);
+        let crib = format!("{}{}", &K3p[..12], "_".repeat(K3p.len() - 12));
+        let found = rotating_grid_search(&ciphertext, &crib, 16, 5);
+        assert_eq!((found[0].width1, found[0].width2, found[0].rotation), (9, 13, Rotation::Counterclockwise));
+        assert_eq!(found[0].plaintext, K3p);
+        let found = rotating_grid_search(&ciphertext, "", 16, 5);
+        assert_eq!(found[0].plaintext, K3p);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{analysis::match_percentage, vig2table};
+use crate::{
+    alphabet::Alphabet,
+    error::{require_key, require_text, Result},
+};
 
 pub fn vigenere(plain_text: &str, key: &str) -> String {
     // Remove all unicode and non-ascii characters from key
@@ -32,3 +35,43 @@ pub fn vigenere(plain_text: &str, key: &str) -> String {
 
 
 
+
+pub fn vigenere_decrypt(cipher_text: &str, key: &str) -> String {
+    let key: String = key.chars().filter(|&c| c.is_ascii_alphabetic()).collect();
+    let key = key.to_ascii_lowercase();
+
+    let key_len = key.len();
+    if key_len == 0 {
+        return String::from(cipher_text);
+    }
+
+    let mut index = 0;
+
+    cipher_text
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphabetic() {
+                let first = if c.is_ascii_lowercase() { b'a' } else { b'A' };
+                let shift = key.as_bytes()[index % key_len] - b'a';
+                index += 1;
+                (first + (c as u8 - first + 26 - shift) % 26) as char
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// `vigenere` with a key of letters only; the unchecked version skips anything else and
+/// leaves the text unchanged when no letters are left.
+pub fn try_vigenere(plain_text: &str, key: &str) -> Result<String> {
+    require_text("Plaintext", plain_text, &Alphabet::standard())?;
+    require_key("Key", key, &Alphabet::standard())?;
+    Ok(vigenere(plain_text, key))
+}
+
+pub fn try_vigenere_decrypt(cipher_text: &str, key: &str) -> Result<String> {
+    require_text("Encrypted", cipher_text, &Alphabet::standard())?;
+    require_key("Key", key, &Alphabet::standard())?;
+    Ok(vigenere_decrypt(cipher_text, key))
+}